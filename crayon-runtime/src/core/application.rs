@@ -0,0 +1,39 @@
+use super::Result;
+
+/// User-facing hooks into the engine's main loop.
+///
+/// All methods have a no-op default implementation, so applications only need
+/// to override the ones they care about.
+pub trait Application {
+    /// Invoked before each frame's rendering.
+    fn on_update(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Invoked after each frame has been submitted to the GPU.
+    fn on_render(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Invoked when the OS is about to suspend the application, e.g. an
+    /// Android activity moving to the background or iOS entering the
+    /// background state. The windowing surface and its GL context may be
+    /// destroyed immediately after this returns, so any GPU-only state that
+    /// can't survive that (render targets kept around for their raw texture
+    /// id, in-flight readbacks, etc.) should be dropped here.
+    fn on_suspend(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Invoked after the application has been resumed and its `Window` has
+    /// been rebound to a fresh native surface via `Window::rebind`. Recreate
+    /// whatever GPU-only state was dropped in `on_suspend`.
+    fn on_resume(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Invoked before the application quits.
+    fn on_exit(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use graphics;
 use super::window;
 use resource;
+use utility;
 
 error_chain!{
     types {
@@ -11,5 +12,6 @@ error_chain!{
         Graphics(graphics::errors::Error, graphics::errors::ErrorKind);
         Window(window::Error, window::ErrorKind);
         Resource(resource::errors::Error, resource::errors::ErrorKind);
+        Utility(utility::errors::Error, utility::errors::ErrorKind);
     }
 }
\ No newline at end of file
@@ -14,12 +14,76 @@ error_chain!{
         Context(glutin::ContextError);
         Creation(glutin::CreationError);
     }
+
+    errors {
+        /// The native surface/context was torn down by the OS, e.g. an Android
+        /// activity moved to the background. Recoverable: call `Window::rebind`
+        /// with a freshly built `glutin::Window` once the app is foregrounded
+        /// again, rather than treating this as a fatal error.
+        ContextLost {
+            description("the window's native surface/context was lost")
+            display("the window's native surface/context was lost and must be rebound")
+        }
+
+        /// `WindowBuilder::with_shared_context` was given a headless `Window`,
+        /// which has no `glutin::Window` to share lists with.
+        CanNotShareHeadlessContext {
+            description("can not share GL objects with a headless context")
+            display("can not share GL objects with a headless context")
+        }
+    }
+}
+
+/// The native surface backing a `Window`, either a real, visible window or a
+/// headless context with no on-screen presentation.
+enum Surface {
+    Visible(glutin::Window),
+    Headless(glutin::HeadlessContext),
+}
+
+impl Surface {
+    fn get_proc_address(&self, func: &str) -> *const () {
+        match *self {
+            Surface::Visible(ref window) => window.get_proc_address(func),
+            Surface::Headless(ref ctx) => ctx.get_proc_address(func),
+        }
+    }
+
+    unsafe fn make_current(&self) -> ::std::result::Result<(), glutin::ContextError> {
+        match *self {
+            Surface::Visible(ref window) => window.make_current(),
+            Surface::Headless(ref ctx) => ctx.make_current(),
+        }
+    }
+
+    fn is_current(&self) -> bool {
+        match *self {
+            Surface::Visible(ref window) => window.is_current(),
+            Surface::Headless(ref ctx) => ctx.is_current(),
+        }
+    }
+
+    fn swap_buffers(&self) -> ::std::result::Result<(), glutin::ContextError> {
+        match *self {
+            Surface::Visible(ref window) => window.swap_buffers(),
+            // There is nothing to present, so there is nothing to do.
+            Surface::Headless(_) => Ok(()),
+        }
+    }
+
+    fn hidpi_factor(&self) -> f32 {
+        match *self {
+            Surface::Visible(ref window) => window.hidpi_factor(),
+            // A headless context is never shown on a scaled display.
+            Surface::Headless(_) => 1.0,
+        }
+    }
 }
 
 /// Represents an OpenGL context and the Window or environment around it, its just
 /// simple wrappers to [glutin](https://github.com/tomaka/glutin) right now.
 pub struct Window {
-    window: Arc<glutin::Window>,
+    surface: Arc<Surface>,
 }
 
 impl Window {
@@ -32,33 +96,39 @@ impl Window {
     /// Returns the address of an OpenGL function.
     /// Contrary to wglGetProcAddress, all available OpenGL functions return an address.
     pub fn get_proc_address(&self, func: &str) -> *const () {
-        self.window.get_proc_address(func)
+        self.surface.get_proc_address(func)
     }
 
     /// Shows the window if it was hidden.
     ///
     /// # Platform-specific
     ///
-    /// Has no effect on mobile platform.
+    /// Has no effect on mobile platform, or on a headless context.
     #[inline]
     pub fn show(&self) {
-        self.window.show();
+        if let Surface::Visible(ref window) = *self.surface {
+            window.show();
+        }
     }
 
     /// Hides the window if it was visible.
     ///
     /// # Platform-specific
     ///
-    /// Has no effect on mobile platform.
+    /// Has no effect on mobile platform, or on a headless context.
     #[inline]
     pub fn hide(&self) {
-        self.window.hide();
+        if let Surface::Visible(ref window) = *self.surface {
+            window.hide();
+        }
     }
 
-    /// Modifies the title of window.
+    /// Modifies the title of window. Has no effect on a headless context.
     #[inline]
     pub fn set_title(&self, title: &str) {
-        self.window.set_title(title);
+        if let Surface::Visible(ref window) = *self.surface {
+            window.set_title(title);
+        }
     }
 
     /// Returns the position of the top-left hand corner of the window relative
@@ -69,32 +139,68 @@ impl Window {
     /// of the desktop.
     /// The coordinates can be negative if the top-left hand corner of the window
     /// is outside of the visible screen region.
-    /// Returns None if the window no longer exists.
+    /// Returns None if the window no longer exists, or this is a headless context.
     #[inline]
     pub fn get_position(&self) -> Option<(i32, i32)> {
-        self.window.get_position()
+        match *self.surface {
+            Surface::Visible(ref window) => window.get_position(),
+            Surface::Headless(_) => None,
+        }
     }
 
-    /// Modifies the position of the window.
+    /// Modifies the position of the window. Has no effect on a headless context.
     #[inline]
     pub fn set_position(&self, x: i32, y: i32) {
-        self.window.set_position(x, y);
+        if let Surface::Visible(ref window) = *self.surface {
+            window.set_position(x, y);
+        }
     }
 
     /// Returns the size in pixels of the client area of the window.
     ///
     /// The client area is the content of the window, excluding the title bar and borders.
     /// These are the dimensions of the frame buffer.
+    /// Returns None if this is a headless context.
     #[inline]
     pub fn dimensions(&self) -> Option<(u32, u32)> {
-        self.window.get_inner_size_pixels()
+        match *self.surface {
+            Surface::Visible(ref window) => window.get_inner_size_pixels(),
+            Surface::Headless(_) => None,
+        }
+    }
+
+    /// Returns the ratio between physical pixels and logical points for the
+    /// monitor this window is on, e.g. `2.0` on a Retina display. `1.0` if
+    /// unknown or if this is a headless context.
+    #[inline]
+    pub fn hidpi_factor(&self) -> f32 {
+        self.surface.hidpi_factor()
+    }
+
+    /// Like `dimensions`, but in logical points instead of physical pixels,
+    /// i.e. `dimensions() / hidpi_factor()`. UI layout code (e.g.
+    /// `CanvasRenderer`'s projection matrix) should use this so widgets keep
+    /// their on-screen size across displays with different pixel densities.
+    #[inline]
+    pub fn logical_dimensions(&self) -> Option<(u32, u32)> {
+        let factor = self.hidpi_factor();
+        self.dimensions()
+            .map(|(w, h)| ((w as f32 / factor) as u32, (h as f32 / factor) as u32))
     }
 
     /// Set the context as the active context in this thread.
+    ///
+    /// # Platform-specific
+    ///
+    /// On Android, the activity lifecycle can tear the native surface down
+    /// while the app is backgrounded. When that happens this returns
+    /// `ErrorKind::ContextLost` instead of propagating the raw `glutin`
+    /// error, so callers can treat it as "wait for `rebind`" rather than a
+    /// fatal failure.
     #[inline]
     pub fn make_current(&self) -> Result<()> {
         unsafe {
-            self.window.make_current()?;
+            self.surface.make_current().map_err(map_context_error)?;
             Ok(())
         }
     }
@@ -102,7 +208,7 @@ impl Window {
     /// Returns true if this context is the current one in this thread.
     #[inline]
     pub fn is_current(&self) -> bool {
-        self.window.is_current()
+        self.surface.is_current()
     }
 
     /// Swaps the buffers in case of double or triple buffering.
@@ -111,13 +217,45 @@ impl Window {
     /// next time the screen is refreshed. However drivers can choose to
     /// override your vsync settings, which means that you can't know in advance
     /// whether swap_buffers will block or not.
+    ///
+    /// Returns `ErrorKind::ContextLost` instead of panicking if the native
+    /// surface was destroyed by the OS, see `rebind`. This is a no-op on a
+    /// headless context, since there is no surface to present.
     #[inline]
     pub fn swap_buffers(&self) -> Result<()> {
-        self.window.swap_buffers()?;
+        self.surface.swap_buffers().map_err(map_context_error)?;
+        Ok(())
+    }
+
+    /// Re-binds this `Window` to a freshly built native surface, e.g. after
+    /// `make_current`/`swap_buffers` reported `ErrorKind::ContextLost` because
+    /// an Android activity was foregrounded again.
+    ///
+    /// This only swaps out the underlying `glutin::Window`; every GPU
+    /// resource handle created through `GraphicsSystemShared` stays valid and
+    /// does not need to be recreated.
+    pub fn rebind(&mut self, window: glutin::Window) -> Result<()> {
+        self.surface = Arc::new(Surface::Visible(window));
+
+        unsafe {
+            self.surface.make_current().map_err(map_context_error)?;
+            gl::load_with(|symbol| self.surface.get_proc_address(symbol) as *const _);
+        }
+
         Ok(())
     }
 }
 
+/// Maps the handful of `glutin::ContextError`s that mean "the native surface
+/// is gone, rebuild it" into `ErrorKind::ContextLost`, so callers can match on
+/// a stable, crate-local error instead of reaching into `glutin`.
+fn map_context_error(err: glutin::ContextError) -> Error {
+    match err {
+        glutin::ContextError::ContextLost => ErrorKind::ContextLost.into(),
+        err => err.into(),
+    }
+}
+
 /// Describes the requested OpenGL context profiles.
 pub enum OpenGLProfile {
     Compatibility,
@@ -132,6 +270,11 @@ pub enum OpenGLAPI {
 }
 
 /// Struct that allow you to build window.
+///
+/// `size` is interpreted in logical points, not physical pixels, so a window
+/// asked for e.g. `(800, 600)` keeps that on-screen size on a Retina/HiDPI
+/// display where `Window::hidpi_factor` is greater than 1. `position` is
+/// stored but not applied by `build`.
 pub struct WindowBuilder {
     title: String,
     position: (i32, i32),
@@ -140,6 +283,7 @@ pub struct WindowBuilder {
     multisample: u16,
     api: OpenGLAPI,
     profile: OpenGLProfile,
+    shared_with: Option<Arc<Surface>>,
 }
 
 impl WindowBuilder {
@@ -175,14 +319,77 @@ impl WindowBuilder {
             builder = builder.with_vsync();
         }
 
+        if let Some(shared) = self.shared_with {
+            match *shared {
+                Surface::Visible(ref w) => builder = builder.with_shared_lists(w),
+                Surface::Headless(_) => bail!(ErrorKind::CanNotShareHeadlessContext),
+            }
+        }
+
         let window = builder.build(&events.underlaying())?;
 
+        // `with_dimensions` above assumes a hidpi factor of 1; glutin only
+        // exposes the real factor once the window exists and is placed on a
+        // monitor, so resize to the true physical size now if it turns out
+        // to be scaled.
+        let factor = window.hidpi_factor();
+        if (factor - 1.0).abs() > ::std::f32::EPSILON {
+            let physical = ((self.size.0 as f32 * factor) as u32,
+                            (self.size.1 as f32 * factor) as u32);
+            window.set_inner_size(physical.0, physical.1);
+            events.emit_dpi_change(factor, physical);
+        }
+
         unsafe {
             window.make_current()?;
             gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
         }
 
-        Ok(Window { window: Arc::new(window) })
+        Ok(Window { surface: Arc::new(Surface::Visible(window)) })
+    }
+
+    /// Builds a `Window` with a current GL context but no visible surface,
+    /// for CI machines without a display server and for render-to-texture
+    /// workloads that never need to present anything.
+    ///
+    /// Prefers a true headless context via glutin's `HeadlessRendererBuilder`;
+    /// where that is unavailable (some Linux/X11 setups require a display
+    /// connection even for headless contexts) falls back to a hidden 1x1
+    /// window, which is visually equivalent but still needs an X server.
+    pub fn build_headless(self, events: &input::Input) -> Result<Window> {
+        let api = match self.api {
+            OpenGLAPI::Lastest => glutin::GlRequest::Latest,
+            OpenGLAPI::GL(major, minor) => {
+                glutin::GlRequest::Specific(glutin::Api::OpenGl, (major, minor))
+            }
+            OpenGLAPI::GLES(major, minor) => {
+                glutin::GlRequest::Specific(glutin::Api::OpenGlEs, (major, minor))
+            }
+        };
+
+        let headless = glutin::HeadlessRendererBuilder::new(self.size.0, self.size.1)
+            .with_gl(api)
+            .build();
+
+        let surface = match headless {
+            Ok(ctx) => Surface::Headless(ctx),
+            Err(_) => {
+                let window = glutin::WindowBuilder::new()
+                    .with_title(self.title.clone())
+                    .with_dimensions(1, 1)
+                    .with_visibility(false)
+                    .with_gl(api)
+                    .build(&events.underlaying())?;
+                Surface::Visible(window)
+            }
+        };
+
+        unsafe {
+            surface.make_current().map_err(map_context_error)?;
+            gl::load_with(|symbol| surface.get_proc_address(symbol) as *const _);
+        }
+
+        Ok(Window { surface: Arc::new(surface) })
     }
 
     /// Requests a specific title for the window.
@@ -227,6 +434,22 @@ impl WindowBuilder {
         self.api = api;
         self
     }
+
+    /// Requests that the built `Window` share GL objects (textures, buffers,
+    /// shaders) with `other`, so a single `GraphicsSystemShared` can drive
+    /// several windows, e.g. a main viewport plus editor tool windows.
+    ///
+    /// # Threading
+    ///
+    /// Only one context per thread may be current at a time. When driving
+    /// more than one shared window from the same thread, call `make_current`
+    /// on the target `Window` before every `swap_buffers`, and do not assume
+    /// the previously-current window stays current across that switch.
+    #[inline]
+    pub fn with_shared_context(&mut self, other: &Window) -> &mut Self {
+        self.shared_with = Some(other.surface.clone());
+        self
+    }
 }
 
 impl Default for WindowBuilder {
@@ -239,6 +462,7 @@ impl Default for WindowBuilder {
             multisample: 0,
             api: OpenGLAPI::Lastest,
             profile: OpenGLProfile::Core,
+            shared_with: None,
         }
     }
 }
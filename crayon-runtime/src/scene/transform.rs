@@ -20,6 +20,13 @@ pub struct Transform {
     next_sib: Option<Entity>,
     prev_sib: Option<Entity>,
     first_child: Option<Entity>,
+    /// World-space pose as of the last `Transform::solve`. Only valid to read
+    /// once `dirty` is `false`.
+    cached_world: math::Decomposed<math::Vector3<f32>, math::Quaternion<f32>>,
+    /// Set whenever `decomposed` or the hierarchy changes; cleared by
+    /// `Transform::solve`. A dirty node forces its whole subtree to
+    /// recompute, since every descendant's `cached_world` depends on it.
+    dirty: bool,
 }
 
 /// Declare `Transform` as component with compact vec storage.
@@ -33,6 +40,8 @@ impl Default for Transform {
             next_sib: None,
             prev_sib: None,
             first_child: None,
+            cached_world: math::Decomposed::one(),
+            dirty: true,
         }
     }
 }
@@ -46,6 +55,7 @@ impl Transform {
     #[inline]
     pub fn set_scale(&mut self, scale: f32) {
         self.decomposed.scale = scale;
+        self.dirty = true;
     }
 
     #[inline]
@@ -58,6 +68,7 @@ impl Transform {
         where T: Borrow<math::Vector3<f32>>
     {
         self.decomposed.disp = *position.borrow();
+        self.dirty = true;
     }
 
     #[inline]
@@ -65,6 +76,7 @@ impl Transform {
         where T: Borrow<math::Vector3<f32>>
     {
         self.decomposed.disp += *disp.borrow();
+        self.dirty = true;
     }
 
     #[inline]
@@ -77,6 +89,7 @@ impl Transform {
         where T: Borrow<math::Quaternion<f32>>
     {
         self.decomposed.rot = *rotation.borrow();
+        self.dirty = true;
     }
 
     #[inline]
@@ -84,6 +97,13 @@ impl Transform {
         self.parent
     }
 
+    /// Returns true if this node's world pose (or that of an ancestor as of
+    /// when it was last touched) has changed since the last `Transform::solve`.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
     // Return ture if this is the leaf of a hierarchy, aka. has no child.
     #[inline]
     pub fn is_leaf(&self) -> bool {
@@ -133,6 +153,7 @@ impl Transform {
                 let child = arena.get_unchecked_mut(*child);
                 child.parent = Some(parent);
                 child.next_sib = next_sib;
+                child.dirty = true;
             }
 
             // Revert to world pose.
@@ -144,11 +165,171 @@ impl Transform {
         }
     }
 
+    /// Attach `child` to `parent`, after its existing children (at the tail of
+    /// the sibling chain), preserving whatever order the parent's children
+    /// were already in.
+    ///
+    /// See `set_parent` for `keep_world_pose`.
+    pub fn append_child(mut arena: &mut ArenaGetter<Transform>,
+                        child: Entity,
+                        parent: Entity,
+                        keep_world_pose: bool)
+                        -> Result<()> {
+        unsafe {
+            if arena.get(*child).is_none() || arena.get(*parent).is_none() {
+                bail!(ErrorKind::NonTransformFound);
+            }
+
+            if child == parent {
+                bail!(ErrorKind::CanNotAttachSelfAsParent);
+            }
+
+            let decomposed = Transform::world_decomposed(&arena, child);
+            Transform::remove_from_parent(arena, child)?;
+
+            let last = Transform::children(&arena, parent).last();
+            if let Some(last) = last {
+                let node = arena.get_unchecked_mut(*last);
+                node.next_sib = Some(child);
+
+                let child_node = arena.get_unchecked_mut(*child);
+                child_node.parent = Some(parent);
+                child_node.prev_sib = Some(last);
+                child_node.dirty = true;
+            } else {
+                arena.get_unchecked_mut(*parent).first_child = Some(child);
+                let child_node = arena.get_unchecked_mut(*child);
+                child_node.parent = Some(parent);
+                child_node.dirty = true;
+            }
+
+            if keep_world_pose {
+                Transform::set_world_decomposed(&mut arena, child, &decomposed)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Attach `child` to `parent`, before its existing children (at the head
+    /// of the sibling chain). Equivalent to `set_parent(arena, child,
+    /// Some(parent), keep_world_pose)`.
+    pub fn prepend_child(arena: &mut ArenaGetter<Transform>,
+                         child: Entity,
+                         parent: Entity,
+                         keep_world_pose: bool)
+                         -> Result<()> {
+        Transform::set_parent(arena, child, Some(parent), keep_world_pose)
+    }
+
+    /// Splices `node` into `sibling`'s sibling chain immediately before it,
+    /// under `sibling`'s current parent. Fails if `sibling` is a root, since
+    /// there would be no parent to attach `node` under.
+    pub fn insert_before(mut arena: &mut ArenaGetter<Transform>,
+                         node: Entity,
+                         sibling: Entity,
+                         keep_world_pose: bool)
+                         -> Result<()> {
+        unsafe {
+            if arena.get(*node).is_none() || arena.get(*sibling).is_none() {
+                bail!(ErrorKind::NonTransformFound);
+            }
+
+            if node == sibling {
+                bail!(ErrorKind::CanNotAttachSelfAsParent);
+            }
+
+            let parent = arena.get_unchecked(*sibling).parent;
+            let parent = match parent {
+                Some(parent) => parent,
+                None => bail!(ErrorKind::NonTransformFound),
+            };
+
+            let decomposed = Transform::world_decomposed(&arena, node);
+            Transform::remove_from_parent(arena, node)?;
+
+            let prev_sib = arena.get_unchecked(*sibling).prev_sib;
+
+            {
+                let node_mut = arena.get_unchecked_mut(*node);
+                node_mut.parent = Some(parent);
+                node_mut.prev_sib = prev_sib;
+                node_mut.next_sib = Some(sibling);
+                node_mut.dirty = true;
+            }
+
+            arena.get_unchecked_mut(*sibling).prev_sib = Some(node);
+
+            if let Some(prev_sib) = prev_sib {
+                arena.get_unchecked_mut(*prev_sib).next_sib = Some(node);
+            } else {
+                arena.get_unchecked_mut(*parent).first_child = Some(node);
+            }
+
+            if keep_world_pose {
+                Transform::set_world_decomposed(&mut arena, node, &decomposed)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Splices `node` into `sibling`'s sibling chain immediately after it,
+    /// under `sibling`'s current parent. Fails if `sibling` is a root, since
+    /// there would be no parent to attach `node` under.
+    pub fn insert_after(mut arena: &mut ArenaGetter<Transform>,
+                        node: Entity,
+                        sibling: Entity,
+                        keep_world_pose: bool)
+                        -> Result<()> {
+        unsafe {
+            if arena.get(*node).is_none() || arena.get(*sibling).is_none() {
+                bail!(ErrorKind::NonTransformFound);
+            }
+
+            if node == sibling {
+                bail!(ErrorKind::CanNotAttachSelfAsParent);
+            }
+
+            let parent = arena.get_unchecked(*sibling).parent;
+            let parent = match parent {
+                Some(parent) => parent,
+                None => bail!(ErrorKind::NonTransformFound),
+            };
+
+            let decomposed = Transform::world_decomposed(&arena, node);
+            Transform::remove_from_parent(arena, node)?;
+
+            let next_sib = arena.get_unchecked(*sibling).next_sib;
+
+            {
+                let node_mut = arena.get_unchecked_mut(*node);
+                node_mut.parent = Some(parent);
+                node_mut.prev_sib = Some(sibling);
+                node_mut.next_sib = next_sib;
+                node_mut.dirty = true;
+            }
+
+            arena.get_unchecked_mut(*sibling).next_sib = Some(node);
+
+            if let Some(next_sib) = next_sib {
+                arena.get_unchecked_mut(*next_sib).prev_sib = Some(node);
+            }
+
+            if keep_world_pose {
+                Transform::set_world_decomposed(&mut arena, node, &decomposed)?;
+            }
+
+            Ok(())
+        }
+    }
+
     /// Detach a transform from its parent and siblings. Children are not affected.
     pub fn remove_from_parent(arena: &mut ArenaGetter<Transform>, handle: Entity) -> Result<()> {
         unsafe {
             let (parent, next_sib, prev_sib) = {
                 if let Some(node) = arena.get_mut(*handle) {
+                    node.dirty = true;
                     (node.parent.take(), node.next_sib.take(), node.prev_sib.take())
                 } else {
                     bail!(ErrorKind::NonTransformFound);
@@ -170,6 +351,127 @@ impl Transform {
         }
     }
 
+    /// Removes `handle` from the hierarchy, following `behavior` to decide
+    /// what happens to its children. Returns the list of `Entity`s the caller
+    /// should despawn: empty unless `behavior` is `DropDescendants`, in which
+    /// case it is `handle` followed by its descendants in tree order.
+    pub fn remove(arena: &mut ArenaGetter<Transform>,
+                  handle: Entity,
+                  behavior: RemoveBehavior)
+                  -> Result<Vec<Entity>> {
+        unsafe {
+            if arena.get(*handle).is_none() {
+                bail!(ErrorKind::NonTransformFound);
+            }
+
+            match behavior {
+                RemoveBehavior::OrphanChildren => {
+                    let children = Transform::children(&arena, handle).collect::<Vec<_>>();
+                    let decomposed: Vec<_> = children.iter()
+                        .map(|&v| Transform::world_decomposed(&arena, v))
+                        .collect();
+
+                    for (child, decomposed) in children.iter().zip(decomposed.iter()) {
+                        {
+                            let node = arena.get_unchecked_mut(**child);
+                            node.parent = None;
+                            node.dirty = true;
+                        }
+                        Transform::set_world_decomposed(arena, *child, decomposed)?;
+                    }
+
+                    arena.get_unchecked_mut(*handle).first_child = None;
+                    Transform::remove_from_parent(arena, handle)?;
+                    Ok(Vec::new())
+                }
+
+                RemoveBehavior::ReparentChildren => {
+                    let parent = arena.get_unchecked(*handle).parent;
+                    let next_sib = arena.get_unchecked(*handle).next_sib;
+                    let children = Transform::children(&arena, handle).collect::<Vec<_>>();
+                    let decomposed: Vec<_> = children.iter()
+                        .map(|&v| Transform::world_decomposed(&arena, v))
+                        .collect();
+
+                    arena.get_unchecked_mut(*handle).first_child = None;
+                    Transform::remove_from_parent(arena, handle)?;
+
+                    if let Some(parent) = parent {
+                        for (child, decomposed) in children.iter().zip(decomposed.iter()) {
+                            if let Some(next_sib) = next_sib {
+                                Transform::insert_before(arena, *child, next_sib, false)?;
+                            } else {
+                                Transform::append_child(arena, *child, parent, false)?;
+                            }
+                            Transform::set_world_decomposed(arena, *child, decomposed)?;
+                        }
+                    } else {
+                        for (child, decomposed) in children.iter().zip(decomposed.iter()) {
+                            {
+                                let node = arena.get_unchecked_mut(**child);
+                                node.parent = None;
+                                node.dirty = true;
+                            }
+                            Transform::set_world_decomposed(arena, *child, decomposed)?;
+                        }
+                    }
+
+                    Ok(Vec::new())
+                }
+
+                RemoveBehavior::DropDescendants => {
+                    let mut dead: Vec<_> = Transform::descendants(&arena, handle).collect();
+                    Transform::remove_from_parent(arena, handle)?;
+                    dead.insert(0, handle);
+                    Ok(dead)
+                }
+            }
+        }
+    }
+
+    /// Recomputes `cached_world` for every dirty node reachable from `roots`,
+    /// so that `world_position`/`world_rotation`/`world_scale`/`transform_*`
+    /// are O(1) afterwards instead of re-walking ancestors on every call.
+    ///
+    /// Each root is expected to actually be a root (`is_root() == true`); a
+    /// non-root entry is not rejected, but starts the walk as if its own
+    /// `decomposed` were the world pose, ignoring its real ancestors.
+    ///
+    /// Marking a node dirty forces its whole subtree to recompute: the
+    /// pre-order walk carries whether an ancestor was already dirty down the
+    /// recursion and treats the node as dirty too when it is.
+    pub fn solve<I>(arena: &mut ArenaGetter<Transform>, roots: I)
+        where I: IntoIterator<Item = Entity>
+    {
+        for root in roots {
+            unsafe { Transform::solve_recursive(arena, root, math::Decomposed::one(), false); }
+        }
+    }
+
+    unsafe fn solve_recursive(arena: &mut ArenaGetter<Transform>,
+                              handle: Entity,
+                              parent_world: math::Decomposed<math::Vector3<f32>,
+                                                              math::Quaternion<f32>>,
+                              parent_dirty: bool) {
+        let (world, dirty, first_child) = {
+            let node = arena.get_unchecked_mut(*handle);
+            let dirty = parent_dirty || node.dirty;
+
+            if dirty {
+                node.cached_world = node.decomposed.concat(&parent_world);
+                node.dirty = false;
+            }
+
+            (node.cached_world, dirty, node.first_child)
+        };
+
+        let mut cursor = first_child;
+        while let Some(child) = cursor {
+            Transform::solve_recursive(arena, child, world, dirty);
+            cursor = arena.get_unchecked(*child).next_sib;
+        }
+    }
+
     /// Return an iterator of references to its ancestors.
     pub fn ancestors<'a, 'b>(arena: &'a ArenaGetter<'b, Transform>,
                              handle: Entity)
@@ -244,16 +546,10 @@ impl Transform {
 
     /// Get the scale of `Transform` in world space.
     pub fn world_scale(arena: &ArenaGetter<Transform>, handle: Entity) -> Result<f32> {
-        unsafe {
-            if let Some(transform) = arena.get(*handle) {
-                let mut scale = transform.scale();
-                for v in Transform::ancestors(arena, handle) {
-                    scale *= arena.get_unchecked(*v).scale();
-                }
-                Ok(scale)
-            } else {
-                bail!(ErrorKind::NonTransformFound);
-            }
+        if let Some(transform) = arena.get(*handle) {
+            Ok(transform.cached_world.scale)
+        } else {
+            bail!(ErrorKind::NonTransformFound);
         }
     }
 
@@ -280,12 +576,10 @@ impl Transform {
     pub fn world_position(arena: &ArenaGetter<Transform>,
                           handle: Entity)
                           -> Result<math::Vector3<f32>> {
-        unsafe {
-            if arena.get(*handle).is_some() {
-                Ok(Transform::world_decomposed(arena, handle).disp)
-            } else {
-                bail!(ErrorKind::NonTransformFound);
-            }
+        if let Some(transform) = arena.get(*handle) {
+            Ok(transform.cached_world.disp)
+        } else {
+            bail!(ErrorKind::NonTransformFound);
         }
     }
 
@@ -316,16 +610,10 @@ impl Transform {
     pub fn world_rotation(arena: &ArenaGetter<Transform>,
                           handle: Entity)
                           -> Result<math::Quaternion<f32>> {
-        unsafe {
-            if let Some(transform) = arena.get(*handle) {
-                let mut rotation = transform.rotation();
-                for v in Transform::ancestors(arena, handle) {
-                    rotation = rotation * arena.get_unchecked(*v).rotation();
-                }
-                Ok(rotation)
-            } else {
-                bail!(ErrorKind::NonTransformFound);
-            }
+        if let Some(transform) = arena.get(*handle) {
+            Ok(transform.cached_world.rot)
+        } else {
+            bail!(ErrorKind::NonTransformFound);
         }
     }
 
@@ -337,13 +625,10 @@ impl Transform {
                             handle: Entity,
                             vec: math::Vector3<f32>)
                             -> Result<math::Vector3<f32>> {
-        unsafe {
-            if arena.get(*handle).is_some() {
-                let decomposed = Transform::world_decomposed(arena, handle);
-                Ok(decomposed.transform_vector(vec))
-            } else {
-                bail!(ErrorKind::NonTransformFound);
-            }
+        if let Some(transform) = arena.get(*handle) {
+            Ok(transform.cached_world.transform_vector(vec))
+        } else {
+            bail!(ErrorKind::NonTransformFound);
         }
     }
 
@@ -352,13 +637,11 @@ impl Transform {
                            handle: Entity,
                            vec: math::Vector3<f32>)
                            -> Result<math::Vector3<f32>> {
-        unsafe {
-            if arena.get(*handle).is_some() {
-                let decomposed = Transform::world_decomposed(&arena, handle);
-                Ok(decomposed.rot * (vec * decomposed.scale) + decomposed.disp)
-            } else {
-                bail!(ErrorKind::NonTransformFound);
-            }
+        if let Some(transform) = arena.get(*handle) {
+            let decomposed = transform.cached_world;
+            Ok(decomposed.rot * (vec * decomposed.scale) + decomposed.disp)
+        } else {
+            bail!(ErrorKind::NonTransformFound);
         }
     }
 
@@ -415,6 +698,24 @@ impl Transform {
         }
         decomposed
     }
+
+    /// Freezes the whole hierarchy into a `TransformSnapshot`: a
+    /// structurally-shared, copy-on-write copy that is safe to read from
+    /// another thread or stash on an undo stack while the live `arena` keeps
+    /// mutating. Cloning the returned snapshot is O(1).
+    pub fn snapshot(arena: &ArenaGetter<Transform>) -> TransformSnapshot {
+        let mut nodes = Vec::new();
+        for v in arena.iter() {
+            let node = unsafe { *arena.get_unchecked(*v) };
+            let index = *v as usize;
+            if nodes.len() <= index {
+                nodes.resize(index + 1, None);
+            }
+            nodes[index] = Some(node);
+        }
+
+        TransformSnapshot { nodes: pvec::Vector::from_slice(&nodes) }
+    }
 }
 
 pub struct Ancestors<'a, 'b>
@@ -441,6 +742,22 @@ impl<'a, 'b> Iterator for Ancestors<'a, 'b>
     }
 }
 
+/// Controls what happens to a node's children when it is removed via
+/// `Transform::remove`.
+pub enum RemoveBehavior {
+    /// Children are detached and become roots; their parent pointers are
+    /// cleared but they otherwise keep their world pose.
+    OrphanChildren,
+    /// Children are spliced into the removed node's former parent, at the
+    /// removed node's former position, preserving their sibling order and
+    /// world pose.
+    ReparentChildren,
+    /// The removed node's whole subtree is dropped from the arena; the
+    /// caller is handed every descendant `Entity` in tree order so it can
+    /// despawn them from the ECS world.
+    DropDescendants,
+}
+
 /// An iterator of references to its children.
 pub struct Children<'a, 'b>
     where 'a: 'b
@@ -510,4 +827,154 @@ impl<'a, 'b> Iterator for Descendants<'a, 'b>
             return ::std::mem::replace(&mut self.cursor, None);
         }
     }
+}
+
+/// A minimal persistent vector with structural sharing, modeled after rpds'
+/// trie-backed `Vector`. Built once from a slice; after that, `Clone` is O(1)
+/// (every node is `Arc`-shared) and reads are O(log n). This is the only
+/// operation `TransformSnapshot` needs -- it is not a general-purpose
+/// persistent collection, e.g. there is no persistent `push`/`update`.
+mod pvec {
+    use std::sync::Arc;
+
+    const BITS: usize = 5;
+    const WIDTH: usize = 1 << BITS;
+    const MASK: usize = WIDTH - 1;
+
+    enum Node<T> {
+        Branch(Vec<Arc<Node<T>>>),
+        Leaf(Vec<T>),
+    }
+
+    pub struct Vector<T> {
+        root: Arc<Node<T>>,
+        len: usize,
+        shift: usize,
+    }
+
+    impl<T> Clone for Vector<T> {
+        fn clone(&self) -> Self {
+            Vector {
+                root: self.root.clone(),
+                len: self.len,
+                shift: self.shift,
+            }
+        }
+    }
+
+    impl<T: Clone> Vector<T> {
+        /// Builds a trie bottom-up from `items`: leaves hold up to `WIDTH`
+        /// elements each, and each further level groups up to `WIDTH` nodes
+        /// from the level below, until a single root remains.
+        pub fn from_slice(items: &[T]) -> Self {
+            if items.is_empty() {
+                return Vector {
+                    root: Arc::new(Node::Leaf(Vec::new())),
+                    len: 0,
+                    shift: 0,
+                };
+            }
+
+            let mut level: Vec<Arc<Node<T>>> = items.chunks(WIDTH)
+                .map(|chunk| Arc::new(Node::Leaf(chunk.to_vec())))
+                .collect();
+
+            let mut shift = 0;
+            while level.len() > 1 {
+                level = level.chunks(WIDTH)
+                    .map(|chunk| Arc::new(Node::Branch(chunk.to_vec())))
+                    .collect();
+                shift += BITS;
+            }
+
+            Vector {
+                root: level.into_iter().next().unwrap(),
+                len: items.len(),
+                shift: shift,
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn get(&self, index: usize) -> Option<&T> {
+            if index >= self.len {
+                return None;
+            }
+
+            let mut node = &*self.root;
+            let mut shift = self.shift;
+            loop {
+                match *node {
+                    Node::Branch(ref children) => {
+                        node = &children[(index >> shift) & MASK];
+                        shift -= BITS;
+                    }
+                    Node::Leaf(ref values) => {
+                        return values.get(index & MASK);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A frozen, structurally-shared copy of the transform hierarchy, taken by
+/// `Transform::snapshot`. `Clone` is O(1); a later mutation to the live arena
+/// the snapshot was built from does not disturb it, which makes snapshots
+/// suitable for lock-free reads from another thread or for an undo stack.
+#[derive(Clone)]
+pub struct TransformSnapshot {
+    nodes: pvec::Vector<Option<Transform>>,
+}
+
+impl TransformSnapshot {
+    fn get(&self, handle: Entity) -> Option<&Transform> {
+        self.nodes.get(*handle as usize).and_then(|v| v.as_ref())
+    }
+
+    /// Returns the chain of ancestors of `handle`, nearest parent first.
+    pub fn ancestors(&self, handle: Entity) -> Vec<Entity> {
+        let mut out = Vec::new();
+        let mut cursor = self.get(handle).and_then(|v| v.parent);
+        while let Some(v) = cursor {
+            out.push(v);
+            cursor = self.get(v).and_then(|v| v.parent);
+        }
+        out
+    }
+
+    /// Returns the direct children of `handle`, in sibling order.
+    pub fn children(&self, handle: Entity) -> Vec<Entity> {
+        let mut out = Vec::new();
+        let mut cursor = self.get(handle).and_then(|v| v.first_child);
+        while let Some(v) = cursor {
+            out.push(v);
+            cursor = self.get(v).and_then(|v| v.next_sib);
+        }
+        out
+    }
+
+    /// Returns every descendant of `handle`, in tree (depth-first) order.
+    pub fn descendants(&self, handle: Entity) -> Vec<Entity> {
+        let mut out = Vec::new();
+        let mut stack: Vec<_> = self.children(handle).into_iter().rev().collect();
+        while let Some(v) = stack.pop() {
+            out.push(v);
+            stack.extend(self.children(v).into_iter().rev());
+        }
+        out
+    }
+
+    /// Returns `handle`'s pose in world space as of when the snapshot was taken.
+    pub fn world_decomposed(&self,
+                            handle: Entity)
+                            -> Option<math::Decomposed<math::Vector3<f32>, math::Quaternion<f32>>> {
+        let mut decomposed = self.get(handle)?.decomposed;
+        for v in self.ancestors(handle) {
+            decomposed = decomposed.concat(&self.get(v)?.decomposed);
+        }
+        Some(decomposed)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+error_chain!{
+    types {
+        Error, ErrorKind, ResultExt, Result;
+    }
+
+    errors {
+        /// The `Handle` is nil and does not refer to any object.
+        InvalidHandle {
+            description("the handle is nil")
+            display("the handle is nil and does not refer to any object")
+        }
+
+        /// The `Handle` is stale, its slot has since been recycled.
+        HandleStale {
+            description("the handle is stale")
+            display("the handle is stale, its underlying storage has been freed")
+        }
+    }
+}
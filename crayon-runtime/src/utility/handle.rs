@@ -1,6 +1,8 @@
 use std::ops::Deref;
 use std::borrow::Borrow;
 
+use super::errors::*;
+
 /// `HandleIndex` type is arbitrary. Keeping it 32-bits allows for
 /// a single 64-bits word per `Handle`.
 pub type HandleIndex = u32;
@@ -59,6 +61,19 @@ impl Handle {
     pub fn version(&self) -> HandleIndex {
         self.version
     }
+
+    /// Asserts that this `Handle` is valid, panicking with the caller's location
+    /// (rather than this helper's) if it is nil.
+    ///
+    /// This mirrors the way slice-indexing attributes out-of-bounds panics to the
+    /// caller instead of the library internals, and should be reused by every
+    /// pool/container that resolves a `Handle` into storage.
+    #[inline]
+    #[track_caller]
+    pub fn expect_valid(&self) -> &Self {
+        assert!(self.is_valid(), "attempted to resolve a nil `Handle`.");
+        self
+    }
 }
 
 impl Deref for Handle {
@@ -108,6 +123,224 @@ macro_rules! impl_handle {
     )
 }
 
+/// A generational allocator that produces and reclaims `Handle`s from a dense
+/// free-list of slots.
+///
+/// Every slot carries its own version counter, which is bumped on every `free`.
+/// This guarantees that a `Handle` returned by a stale `free`/`is_alive` check
+/// can never be confused with a handle minted after the slot was recycled.
+#[derive(Debug, Clone, Default)]
+pub struct HandleAllocator {
+    versions: Vec<HandleIndex>,
+    frees: Vec<HandleIndex>,
+}
+
+impl HandleAllocator {
+    /// Constructs a new, empty `HandleAllocator`.
+    #[inline]
+    pub fn new() -> Self {
+        HandleAllocator {
+            versions: Vec::new(),
+            frees: Vec::new(),
+        }
+    }
+
+    /// Creates a new `Handle`, either recycling a freed slot or growing the pool.
+    pub fn create(&mut self) -> Handle {
+        if let Some(index) = self.frees.pop() {
+            Handle::new(index, self.versions[index as usize])
+        } else {
+            let index = self.versions.len() as HandleIndex;
+            // Version `0` is reserved for `Handle::nil()`, so every live slot
+            // starts its life at version `1`.
+            self.versions.push(1);
+            Handle::new(index, 1)
+        }
+    }
+
+    /// Frees a `Handle`, returning its index to the free list.
+    ///
+    /// Returns `false` without mutating any state if `handle` is stale (i.e. its
+    /// version no longer matches the slot's live version), so a double-free or a
+    /// use-after-free can never corrupt the free list.
+    pub fn free(&mut self, handle: Handle) -> bool {
+        if !self.is_alive(handle) {
+            return false;
+        }
+
+        let index = handle.index() as usize;
+        // Skip version `0` on wrap-around so a recycled slot never collides
+        // with a handle that has already been handed out.
+        self.versions[index] = self.versions[index].wrapping_add(1).max(1);
+        self.frees.push(handle.index());
+        true
+    }
+
+    /// Returns true if `handle` still points at a live slot.
+    #[inline]
+    pub fn is_alive(&self, handle: Handle) -> bool {
+        self.versions
+            .get(handle.index() as usize)
+            .map_or(false, |&version| version == handle.version() && version > 0)
+    }
+
+    /// Returns the number of slots that have ever been allocated.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.versions.len()
+    }
+
+    /// Iterates over every currently live `Handle`.
+    pub fn iter(&self) -> HandleAllocatorIter {
+        HandleAllocatorIter {
+            allocator: self,
+            index: 0,
+        }
+    }
+}
+
+/// An iterator over the currently live handles of a `HandleAllocator`.
+pub struct HandleAllocatorIter<'a> {
+    allocator: &'a HandleAllocator,
+    index: usize,
+}
+
+impl<'a> Iterator for HandleAllocatorIter<'a> {
+    type Item = Handle;
+
+    fn next(&mut self) -> Option<Handle> {
+        while self.index < self.allocator.versions.len() {
+            let index = self.index as HandleIndex;
+            let version = self.allocator.versions[self.index];
+            self.index += 1;
+
+            if version > 0 && !self.allocator.frees.contains(&index) {
+                return Some(Handle::new(index, version));
+            }
+        }
+
+        None
+    }
+}
+
+/// A dense, `Handle`-indexed container whose `get`/`get_mut` never panic on a
+/// stale or nil lookup (indexing with `[]` does, see the `Index` impl).
+///
+/// Elements are stored alongside the version they were inserted with, so a
+/// `Handle` whose version has since been bumped (by a `free`/recycle elsewhere)
+/// is rejected instead of silently resolving to whatever now lives at that index.
+#[derive(Debug, Clone, Default)]
+pub struct HandleMap<T> {
+    objects: Vec<Option<(HandleIndex, T)>>,
+}
+
+impl<T> HandleMap<T> {
+    /// Constructs a new, empty `HandleMap`.
+    #[inline]
+    pub fn new() -> Self {
+        HandleMap { objects: Vec::new() }
+    }
+
+    /// Inserts `value` at the slot addressed by `handle`, growing the container
+    /// as needed.
+    pub fn insert(&mut self, handle: Handle, value: T) {
+        let index = handle.index() as usize;
+        if index >= self.objects.len() {
+            self.objects.resize_with_none(index + 1);
+        }
+
+        self.objects[index] = Some((handle.version(), value));
+    }
+
+    /// Removes and returns the value addressed by `handle`, if any.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let index = handle.index() as usize;
+        let matches = self.objects
+            .get(index)
+            .and_then(|v| v.as_ref())
+            .map_or(false, |&(version, _)| version == handle.version());
+
+        if matches {
+            self.objects[index].take().map(|(_, v)| v)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the value addressed by `handle`.
+    pub fn get(&self, handle: Handle) -> Result<&T> {
+        self.validate(handle)?;
+        Ok(&self.objects[handle.index() as usize].as_ref().unwrap().1)
+    }
+
+    /// Returns a mutable reference to the value addressed by `handle`.
+    pub fn get_mut(&mut self, handle: Handle) -> Result<&mut T> {
+        self.validate(handle)?;
+        Ok(&mut self.objects[handle.index() as usize].as_mut().unwrap().1)
+    }
+
+    fn validate(&self, handle: Handle) -> Result<()> {
+        if !handle.is_valid() {
+            bail!(ErrorKind::InvalidHandle);
+        }
+
+        match self.objects.get(handle.index() as usize) {
+            Some(&Some((version, _))) if version == handle.version() => Ok(()),
+            _ => bail!(ErrorKind::HandleStale),
+        }
+    }
+}
+
+impl<T> ::std::ops::Index<Handle> for HandleMap<T> {
+    type Output = T;
+
+    /// Resolves `handle` against this map, panicking at the caller's location
+    /// (see `Handle::expect_valid`) if it is stale or nil.
+    #[track_caller]
+    fn index(&self, handle: Handle) -> &T {
+        handle.expect_valid();
+        // Panics directly here rather than through `unwrap_or_else`'s closure:
+        // `#[track_caller]` doesn't propagate through an intervening closure
+        // call, so routing the stale-handle panic through one would attribute
+        // it to this line instead of the caller's.
+        match self.get(handle) {
+            Ok(v) => v,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+impl<T> ::std::ops::IndexMut<Handle> for HandleMap<T> {
+    /// Resolves `handle` against this map, panicking at the caller's location
+    /// (see `Handle::expect_valid`) if it is stale or nil.
+    #[track_caller]
+    fn index_mut(&mut self, handle: Handle) -> &mut T {
+        handle.expect_valid();
+        // See `Index::index`'s matching comment: panics directly here, not
+        // through `unwrap_or_else`'s closure, to keep `#[track_caller]`
+        // attribution pointing at the caller.
+        match self.get_mut(handle) {
+            Ok(v) => v,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+/// Alias matching the "object set" naming used by some handle-indexed subsystems.
+pub type HandleObjectSet<T> = HandleMap<T>;
+
+trait ResizeWithNone<T> {
+    fn resize_with_none(&mut self, len: usize);
+}
+
+impl<T> ResizeWithNone<T> for Vec<Option<T>> {
+    fn resize_with_none(&mut self, len: usize) {
+        while self.len() < len {
+            self.push(None);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -153,4 +386,75 @@ mod test {
         let h2 = TypeSafeHandle(Handle::default());
         assert_eq!(*h2, Handle::default());
     }
+
+    #[test]
+    fn allocator() {
+        let mut allocator = HandleAllocator::new();
+
+        let h1 = allocator.create();
+        let h2 = allocator.create();
+        assert_ne!(h1, h2);
+        assert!(allocator.is_alive(h1));
+        assert!(allocator.is_alive(h2));
+
+        assert_eq!(allocator.free(h1), true);
+        assert!(!allocator.is_alive(h1));
+
+        // Freeing the same handle twice must be a no-op.
+        assert_eq!(allocator.free(h1), false);
+
+        // The recycled slot gets a new version, so old handles never resurrect.
+        let h3 = allocator.create();
+        assert_eq!(h3.index(), h1.index());
+        assert_ne!(h3.version(), h1.version());
+        assert!(!allocator.is_alive(h1));
+        assert!(allocator.is_alive(h3));
+
+        let live: Vec<_> = allocator.iter().collect();
+        assert_eq!(live.len(), 2);
+        assert!(live.contains(&h2));
+        assert!(live.contains(&h3));
+    }
+
+    #[test]
+    fn handle_map() {
+        let mut allocator = HandleAllocator::new();
+        let mut map = HandleMap::new();
+
+        let h1 = allocator.create();
+        map.insert(h1, "hello");
+        assert_eq!(*map.get(h1).unwrap(), "hello");
+
+        *map.get_mut(h1).unwrap() = "world";
+        assert_eq!(*map.get(h1).unwrap(), "world");
+
+        assert!(map.get(Handle::nil()).is_err());
+
+        allocator.free(h1);
+        let h2 = allocator.create();
+        assert_eq!(h2.index(), h1.index());
+
+        // `h1` is stale now that its slot has been recycled as `h2`.
+        assert!(map.get(h1).is_err());
+        assert_eq!(map.remove(h1), None);
+
+        map.insert(h2, "fresh");
+        assert_eq!(map.remove(h2), Some("fresh"));
+        assert!(map.get(h2).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn handle_map_index_panics_on_stale_handle() {
+        let mut allocator = HandleAllocator::new();
+        let mut map = HandleMap::new();
+
+        let h1 = allocator.create();
+        map.insert(h1, 1);
+        allocator.free(h1);
+        let h2 = allocator.create();
+        map.insert(h2, 2);
+
+        let _ = map[h1];
+    }
 }
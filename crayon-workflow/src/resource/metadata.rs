@@ -1,5 +1,6 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use sha2::{Digest, Sha256};
 use uuid;
 
 use errors::*;
@@ -16,11 +17,16 @@ pub enum ResourceConcreteMetadata {
 pub struct ResourceMetadata {
     time_created: u64,
     uuid: uuid::Uuid,
+    /// Content digest of the source bytes at ingest/build time, used for
+    /// content-addressed caching and de-duplication. `None` for metadata
+    /// serialized before this field was introduced.
+    #[serde(default)]
+    digest: Option<[u8; 32]>,
     metadata: ResourceConcreteMetadata,
 }
 
 impl ResourceMetadata {
-    pub fn new(metadata: ResourceConcreteMetadata) -> ResourceMetadata {
+    pub fn new(metadata: ResourceConcreteMetadata, bytes: &[u8]) -> ResourceMetadata {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -29,23 +35,33 @@ impl ResourceMetadata {
         ResourceMetadata {
             time_created: timestamp,
             uuid: uuid::Uuid::new_v4(),
+            digest: Some(Self::digest_of(bytes)),
             metadata: metadata,
         }
     }
 
-    pub fn new_as(tt: Resource) -> ResourceMetadata {
+    pub fn new_as(tt: Resource, bytes: &[u8]) -> ResourceMetadata {
         let concrete = match tt {
             Resource::Bytes => ResourceConcreteMetadata::Bytes(bytes::BytesMetadata::new()),
             Resource::Texture => ResourceConcreteMetadata::Texture(texture::TextureMetadata::new()),
         };
 
-        ResourceMetadata::new(concrete)
+        ResourceMetadata::new(concrete, bytes)
     }
 
     pub fn uuid(&self) -> uuid::Uuid {
         self.uuid
     }
 
+    /// Returns the content digest recorded at build time, if any.
+    ///
+    /// Two `ResourceMetadata`s with identical bytes share a digest even though
+    /// their `uuid`s differ, which allows a build pipeline to skip rebuilding
+    /// unchanged assets by comparing digests instead of re-running `build`.
+    pub fn digest(&self) -> Option<&[u8; 32]> {
+        self.digest.as_ref()
+    }
+
     pub fn is(&self, tt: Resource) -> bool {
         self.file_type() == tt
     }
@@ -57,7 +73,15 @@ impl ResourceMetadata {
         }
     }
 
+    /// Validates `bytes` against the stored content digest (when present)
+    /// before delegating to the concrete metadata's own validation.
     pub fn validate(&self, bytes: &[u8]) -> Result<()> {
+        if let Some(ref digest) = self.digest {
+            if *digest != Self::digest_of(bytes) {
+                bail!("resource bytes do not match the recorded content digest");
+            }
+        }
+
         match &self.metadata {
             &ResourceConcreteMetadata::Bytes(ref metadata) => metadata.validate(&bytes),
             &ResourceConcreteMetadata::Texture(ref metadata) => metadata.validate(&bytes),
@@ -70,4 +94,13 @@ impl ResourceMetadata {
             &ResourceConcreteMetadata::Bytes(ref metadata) => metadata.build(&bytes, &mut out),
         }
     }
+
+    fn digest_of(bytes: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.input(bytes);
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(hasher.result().as_slice());
+        digest
+    }
 }
\ No newline at end of file
@@ -8,6 +8,8 @@ pub struct Lit {
     pub enable: bool,
     /// Is this light casting shadow.
     pub shadow_caster: bool,
+    /// How this light's shadow map is filtered when it is sampled, if at all.
+    pub shadow: ShadowSettings,
     /// Color of the light.
     pub color: math::Color<f32>,
     /// Brightness of the light source, in lumens.
@@ -19,6 +21,55 @@ pub struct Lit {
     pub(crate) transform: Transform,
 }
 
+/// Configures how a shadow-casting `Lit` renders and samples its shadow map.
+///
+/// A `Dir` light's shadow pass uses an orthographic light matrix; a `Spot`
+/// light uses a perspective one sized to its cone. Either way the pass
+/// renders scene depth from the light's viewpoint into a depth texture of
+/// `resolution` pixels, and shading samples it back through `filter`.
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowSettings {
+    /// How shadow-map texels are filtered into a visibility term.
+    pub filter: ShadowFilter,
+    /// Width and height, in texels, of the shadow map render target.
+    pub resolution: u32,
+    /// Constant depth bias, added before the slope-scaled term, to avoid
+    /// shadow acne without introducing excessive peter-panning.
+    pub bias: f32,
+    /// Slope-scaled depth bias, multiplied by the surface's slope relative
+    /// to the light and added on top of `bias`.
+    pub slope_bias: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        ShadowSettings {
+            filter: ShadowFilter::Off,
+            resolution: 1024,
+            bias: 0.002,
+            slope_bias: 0.01,
+        }
+    }
+}
+
+/// The filter used to turn a shadow-map comparison into a soft (or hard)
+/// visibility term.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShadowFilter {
+    /// No shadow-map sampling; `shadow_caster` is ignored.
+    Off,
+    /// A single hardware-filtered 2x2 PCF tap (`sampler2DShadow`-style).
+    Hardware2x2,
+    /// `taps` samples at fixed Poisson-disk offsets around the projected
+    /// texel, each compared independently and averaged for a soft edge.
+    Pcf { taps: u32, radius: f32 },
+    /// Percentage-closer soft shadows: a blocker-search pass estimates the
+    /// average occluder depth within `search_radius`, derives a penumbra
+    /// width from the receiver/blocker/light-size ratio, then runs a `Pcf`
+    /// kernel whose radius is scaled by that width.
+    Pcss { search_radius: f32, light_size: f32, taps: u32 },
+}
+
 /// Enumeration for all light sources.
 #[derive(Debug, Clone, Copy)]
 pub enum LitSource {
@@ -30,7 +81,89 @@ pub enum LitSource {
         radius: f32,
         /// Smoothness of the light-to-dark transition from the center to the radius.
         smoothness: f32,
+        /// How intensity falls off with distance within `radius`.
+        falloff: Falloff,
     },
+    /// A spot light: a point light additionally clipped to a cone.
+    Spot {
+        /// Maximum radius of the spot light's affected data, same role as
+        /// `Point::radius`.
+        radius: f32,
+        /// Smoothness of the light-to-dark transition from the center to the radius.
+        smoothness: f32,
+        /// How intensity falls off with distance within `radius`.
+        falloff: Falloff,
+        /// Half-angle, in radians, within which the cone is at full intensity.
+        inner_cone_angle: f32,
+        /// Half-angle, in radians, beyond which the cone contributes nothing.
+        outer_cone_angle: f32,
+    },
+}
+
+/// How a `Point`/`Spot` light's intensity falls off with distance from its
+/// center, independent of its cone (if any).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Falloff {
+    /// Intensity falls linearly from `1` at the center to `0` at `radius`.
+    Linear,
+    /// Physically-based inverse-square falloff, windowed smoothly down to
+    /// exactly `0` at `radius` so the light can still be culled past it.
+    InverseSquare,
+}
+
+impl Lit {
+    /// Attenuation of this light's intensity at `point`, folding in
+    /// distance falloff and, for `Spot`, the cone's smoothstep edge. `Dir`
+    /// lights are not attenuated by distance and always return `1.0`.
+    pub fn attenuation(&self, point: math::Vector3<f32>) -> f32 {
+        match self.source {
+            LitSource::Dir => 1.0,
+            LitSource::Point { radius, smoothness, falloff } => {
+                let distance = (point - self.transform.position()).magnitude();
+                distance_attenuation(distance, radius, smoothness, falloff)
+            }
+            LitSource::Spot { radius, smoothness, falloff, inner_cone_angle, outer_cone_angle } => {
+                let to_point = point - self.transform.position();
+                let distance = to_point.magnitude();
+                let d = distance_attenuation(distance, radius, smoothness, falloff);
+
+                let cos_angle = self.transform.forward().dot(to_point / distance);
+                let inner_cos = inner_cone_angle.cos();
+                let outer_cos = outer_cone_angle.cos();
+                let cone = ((cos_angle - outer_cos) / (inner_cos - outer_cos)).max(0.0).min(1.0);
+                let cone = cone * cone * (3.0 - 2.0 * cone);
+
+                d * cone
+            }
+        }
+    }
+}
+
+/// Shared distance-falloff term for `Point` and `Spot` lights: `0` at and
+/// beyond `radius`, shaped by `falloff` inside it, with `smoothness`
+/// widening the transition band just inside `radius`.
+fn distance_attenuation(distance: f32, radius: f32, smoothness: f32, falloff: Falloff) -> f32 {
+    if distance >= radius {
+        return 0.0;
+    }
+
+    let base = match falloff {
+        Falloff::Linear => (1.0 - distance / radius).max(0.0),
+        Falloff::InverseSquare => {
+            let window = (1.0 - (distance / radius).powi(4)).max(0.0);
+            window * window / (distance * distance).max(1e-4)
+        }
+    };
+
+    let smooth_start = radius * (1.0 - smoothness).max(0.0);
+    let edge = if smoothness <= 0.0 || distance <= smooth_start {
+        1.0
+    } else {
+        let t = ((radius - distance) / (radius - smooth_start)).max(0.0).min(1.0);
+        t * t * (3.0 - 2.0 * t)
+    };
+
+    base * edge
 }
 
 impl Default for Lit {
@@ -38,6 +171,7 @@ impl Default for Lit {
         Lit {
             enable: true,
             shadow_caster: false,
+            shadow: ShadowSettings::default(),
             color: math::Color::white(),
             intensity: 1.0,
             source: LitSource::Dir,
@@ -0,0 +1,222 @@
+//! A reusable shadow-mapping subsystem: a depth-only view that renders a
+//! light's occluders from its own point of view, plus the CPU-side sampling
+//! parameters -- and a matching GLSL sampling snippet -- the main pass uses
+//! to turn that depth texture into a soft (or hard, or off) visibility term.
+//!
+//! Building the depth pass as a `RenderGraphPass` means it composes with
+//! the rest of a `RenderGraph` like any other pass: the main pass declares
+//! `.reads(resource)` on the name this module assigns the depth target, and
+//! the graph takes care of ordering and aliasing it against other
+//! transient targets.
+
+use crayon::graphics::errors::*;
+use crayon::graphics::{FrameBufferSetup, RenderGraphPass, RenderGraphResource, RenderTextureSetup,
+                        SurfaceHandle};
+
+use renderers::lit::ShadowFilter;
+
+/// Builds the depth-only `RenderGraphPass` that renders a light's occluders
+/// from its own point of view into `setup`/`framebuffer`'s target, named
+/// `resource` so a later pass can `.reads(resource)` it to sample shadows.
+///
+/// Scene traversal -- which occluders exist, their transforms, the light's
+/// view/projection matrix -- is the caller's job: `render_occluders` is
+/// exactly the closure `RenderGraphPass::new` already takes. This function
+/// only fixes the pass's name and declares that it writes `resource`.
+pub fn shadow_map_pass<F>(resource: RenderGraphResource,
+                          setup: RenderTextureSetup,
+                          framebuffer: FrameBufferSetup,
+                          render_occluders: F)
+                          -> RenderGraphPass
+    where F: Fn(SurfaceHandle) -> Result<()> + 'static
+{
+    RenderGraphPass::new(resource, setup, framebuffer, render_occluders).writes(resource)
+}
+
+/// Fixed, rotated Poisson-disc sample offsets shared by `ShadowFilter::Pcf`
+/// and the PCF step of `ShadowFilter::Pcss`. Precomputed rather than
+/// randomized per-pixel, so every fragment samples the same kernel shape,
+/// just scaled by a radius; a shader can still rotate the whole kernel
+/// cheaply per-pixel with a 2x2 matrix built from screen-space noise.
+const POISSON_DISC_16: [(f32, f32); 16] =
+    [(-0.94201624, -0.39906216),
+     (0.94558609, -0.76890725),
+     (-0.094184101, -0.92938870),
+     (0.34495938, 0.29387760),
+     (-0.91588581, 0.45771432),
+     (-0.81544232, -0.87912464),
+     (-0.38277543, 0.27676845),
+     (0.97484398, 0.75648379),
+     (0.44323325, -0.97511554),
+     (0.53742981, -0.47373420),
+     (-0.26496911, -0.41893023),
+     (0.79197514, 0.19090188),
+     (-0.24188840, 0.99706507),
+     (-0.81409955, 0.91437590),
+     (0.19984126, 0.78641367),
+     (0.14383161, -0.14100790)];
+
+/// Returns the first `taps` entries of the fixed Poisson-disc kernel,
+/// scaled by `radius`. Panics if `taps` exceeds the precomputed kernel size.
+pub fn poisson_disc_taps(taps: u32, radius: f32) -> Vec<(f32, f32)> {
+    assert!((taps as usize) <= POISSON_DISC_16.len(),
+            "only {} Poisson-disc taps are precomputed.",
+            POISSON_DISC_16.len());
+
+    POISSON_DISC_16[..taps as usize]
+        .iter()
+        .map(|&(x, y)| (x * radius, y * radius))
+        .collect()
+}
+
+/// Derives a PCSS penumbra width from a blocker search's average occluder
+/// depth: `penumbra = (receiver_depth - avg_blocker_depth) / avg_blocker_depth
+/// * light_size`, so nearer occluders (smaller `receiver_depth -
+/// avg_blocker_depth`) give a sharper shadow and farther ones a softer one.
+/// Returns `0.0` (a hard edge, no widening) if `avg_blocker_depth` found no
+/// occluders at all.
+pub fn pcss_penumbra(receiver_depth: f32, avg_blocker_depth: f32, light_size: f32) -> f32 {
+    if avg_blocker_depth <= 0.0 {
+        return 0.0;
+    }
+
+    (receiver_depth - avg_blocker_depth) / avg_blocker_depth * light_size
+}
+
+/// Depth bias to subtract before a shadow-map comparison, folding in
+/// `ShadowSettings`'s constant and slope-scaled terms. `normal_dot_light` is
+/// the surface normal dotted with the direction to the light, clamped to
+/// `[0, 1]` by the caller; a grazing-angle surface (small dot product) gets
+/// a larger bias to avoid acne.
+pub fn depth_bias(normal_dot_light: f32, bias: f32, slope_bias: f32) -> f32 {
+    bias + slope_bias * (1.0 - normal_dot_light).max(0.0)
+}
+
+/// Resolves a `ShadowFilter` into the `#define`s
+/// `shader_preprocessor::preprocess` should fold into `SHADOW_SAMPLING_GLSL`
+/// below, e.g. `("SHADOW_FILTER_PCF", "1")`/`("SHADOW_PCF_TAPS", "12")`.
+pub fn shadow_filter_defines(filter: ShadowFilter) -> Vec<(String, String)> {
+    match filter {
+        ShadowFilter::Off => vec![("SHADOW_FILTER_OFF".to_string(), "1".to_string())],
+        ShadowFilter::Hardware2x2 => {
+            vec![("SHADOW_FILTER_HARDWARE_2X2".to_string(), "1".to_string())]
+        }
+        ShadowFilter::Pcf { taps, radius } => {
+            vec![("SHADOW_FILTER_PCF".to_string(), "1".to_string()),
+                 ("SHADOW_PCF_TAPS".to_string(), taps.to_string()),
+                 ("SHADOW_PCF_RADIUS".to_string(), radius.to_string())]
+        }
+        ShadowFilter::Pcss { search_radius, light_size, taps } => {
+            vec![("SHADOW_FILTER_PCSS".to_string(), "1".to_string()),
+                 ("SHADOW_PCSS_SEARCH_RADIUS".to_string(), search_radius.to_string()),
+                 ("SHADOW_PCSS_LIGHT_SIZE".to_string(), light_size.to_string()),
+                 ("SHADOW_PCSS_TAPS".to_string(), taps.to_string())]
+        }
+    }
+}
+
+/// GLSL snippet implementing hardware/PCF/PCSS shadow sampling, selected at
+/// `shader_preprocessor::preprocess` time by the `#define`s
+/// `shadow_filter_defines` emits. `#include`d by a fragment shader wherever
+/// it needs a `shadow(vec4 light_space_pos, float bias)` visibility term.
+/// Directional and spot lights sample a `sampler2DShadow` built from one
+/// `shadow_map_pass`; a point light instead renders one `shadow_map_pass`
+/// per cube face and samples a `samplerCubeShadow`, left to the including
+/// shader since the face selection differs per light type.
+pub const SHADOW_SAMPLING_GLSL: &'static str = r#"
+#if defined(SHADOW_FILTER_OFF)
+float shadow(vec4 light_space_pos, float bias) { return 1.0; }
+
+#elif defined(SHADOW_FILTER_HARDWARE_2X2)
+uniform sampler2DShadow u_ShadowMap;
+float shadow(vec4 light_space_pos, float bias) {
+    vec3 proj = light_space_pos.xyz / light_space_pos.w * 0.5 + 0.5;
+    return texture(u_ShadowMap, vec3(proj.xy, proj.z - bias));
+}
+
+#elif defined(SHADOW_FILTER_PCF)
+uniform sampler2DShadow u_ShadowMap;
+uniform vec2 u_ShadowTaps[SHADOW_PCF_TAPS];
+float shadow(vec4 light_space_pos, float bias) {
+    vec3 proj = light_space_pos.xyz / light_space_pos.w * 0.5 + 0.5;
+    float sum = 0.0;
+    for (int i = 0; i < SHADOW_PCF_TAPS; i++) {
+        vec2 offset = u_ShadowTaps[i] * SHADOW_PCF_RADIUS;
+        sum += texture(u_ShadowMap, vec3(proj.xy + offset, proj.z - bias));
+    }
+    return sum / float(SHADOW_PCF_TAPS);
+}
+
+#elif defined(SHADOW_FILTER_PCSS)
+uniform sampler2D u_ShadowMapDepth;
+uniform sampler2DShadow u_ShadowMap;
+uniform vec2 u_ShadowTaps[SHADOW_PCSS_TAPS];
+
+float blocker_search(vec2 uv, float receiver_depth) {
+    float sum = 0.0;
+    float count = 0.0;
+    for (int i = 0; i < SHADOW_PCSS_TAPS; i++) {
+        vec2 offset = u_ShadowTaps[i] * SHADOW_PCSS_SEARCH_RADIUS;
+        float depth = texture(u_ShadowMapDepth, uv + offset).r;
+        if (depth < receiver_depth) {
+            sum += depth;
+            count += 1.0;
+        }
+    }
+    return count > 0.0 ? sum / count : -1.0;
+}
+
+float shadow(vec4 light_space_pos, float bias) {
+    vec3 proj = light_space_pos.xyz / light_space_pos.w * 0.5 + 0.5;
+    float avg_blocker_depth = blocker_search(proj.xy, proj.z);
+    if (avg_blocker_depth < 0.0) {
+        return 1.0;
+    }
+
+    float penumbra = (proj.z - avg_blocker_depth) / avg_blocker_depth * SHADOW_PCSS_LIGHT_SIZE;
+
+    float sum = 0.0;
+    for (int i = 0; i < SHADOW_PCSS_TAPS; i++) {
+        vec2 offset = u_ShadowTaps[i] * penumbra;
+        sum += texture(u_ShadowMap, vec3(proj.xy + offset, proj.z - bias));
+    }
+    return sum / float(SHADOW_PCSS_TAPS);
+}
+#endif
+"#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn poisson_taps_scale_by_radius() {
+        let taps = poisson_disc_taps(4, 2.0);
+        assert_eq!(taps.len(), 4);
+        for (i, &(x, y)) in taps.iter().enumerate() {
+            assert_eq!(x, POISSON_DISC_16[i].0 * 2.0);
+            assert_eq!(y, POISSON_DISC_16[i].1 * 2.0);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn poisson_taps_rejects_too_many() {
+        poisson_disc_taps(POISSON_DISC_16.len() as u32 + 1, 1.0);
+    }
+
+    #[test]
+    fn penumbra_widens_with_distance_from_blocker() {
+        let near = pcss_penumbra(10.0, 9.0, 1.0);
+        let far = pcss_penumbra(10.0, 5.0, 1.0);
+        assert!(far > near);
+        assert_eq!(pcss_penumbra(10.0, 0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn bias_grows_at_grazing_angles() {
+        let head_on = depth_bias(1.0, 0.002, 0.01);
+        let grazing = depth_bias(0.1, 0.002, 0.01);
+        assert!(grazing > head_on);
+    }
+}
@@ -19,6 +19,9 @@ pub struct CanvasRenderer {
 
     vso: graphics::ViewStateHandle,
     pso: graphics::PipelineStateHandle,
+    // Samples through a `samplerExternalOES` uniform instead of `sampler2D`,
+    // used by `submit_external` for `ExternalTextureTarget::ExternalOES`.
+    external_pso: graphics::PipelineStateHandle,
     vbo: graphics::VertexBufferHandle,
     ibo: graphics::IndexBufferHandle,
 
@@ -27,6 +30,7 @@ pub struct CanvasRenderer {
 
     current_matrix: math::Matrix4<f32>,
     current_texture: Option<graphics::TextureHandle>,
+    current_pso: graphics::PipelineStateHandle,
 }
 
 impl CanvasRenderer {
@@ -53,7 +57,19 @@ impl CanvasRenderer {
 
         let vs = include_str!("../resources/canvas.vs").to_owned();
         let fs = include_str!("../resources/canvas.fs").to_owned();
-        let pso = video.create_pipeline(setup, vs, fs)?;
+        let pso = video.create_pipeline(setup, vs.clone(), fs)?;
+
+        // Same vertex stage and blend state as `pso`; only the fragment stage
+        // differs, swapping `sampler2D` for `samplerExternalOES`.
+        let mut external_setup = graphics::PipelineStateSetup::default();
+        external_setup.layout = layout;
+        external_setup.state.color_blend =
+            Some((graphics::Equation::Add,
+                  graphics::BlendFactor::Value(graphics::BlendValue::SourceAlpha),
+                  graphics::BlendFactor::OneMinusValue(graphics::BlendValue::SourceAlpha)));
+
+        let external_fs = include_str!("../resources/canvas_external_oes.fs").to_owned();
+        let external_pso = video.create_pipeline(external_setup, vs, external_fs)?;
 
         let mut setup = graphics::VertexBufferSetup::default();
         setup.layout = CanvasVertex::layout();
@@ -74,6 +90,7 @@ impl CanvasRenderer {
 
                vso: vso,
                pso: pso,
+               external_pso: external_pso,
                vbo: vbo,
                ibo: ibo,
 
@@ -82,6 +99,7 @@ impl CanvasRenderer {
 
                current_texture: None,
                current_matrix: math::Matrix4::one(),
+               current_pso: pso,
            })
     }
 
@@ -130,6 +148,45 @@ impl CanvasRenderer {
         Ok(())
     }
 
+    /// Like `submit`, but draws through a foreign GL texture id wrapped with
+    /// `graphics::GraphicsSystemShared::create_external_texture`, e.g. a video
+    /// decoder or camera frame uploaded outside this crate. Always flushes the
+    /// current batch first, since swapping `mainTexture` to an external target
+    /// breaks the batching rules `submit` relies on (a GLES `ExternalOES`
+    /// target needs the `external_pso` pipeline, not the regular one).
+    pub fn submit_external(&mut self,
+                            verts: &[CanvasVertex],
+                            idxes: &[u16],
+                            raw_gl_texture: u32,
+                            target: graphics::ExternalTextureTarget)
+                            -> Result<()> {
+        self.flush()?;
+
+        let texture = self.video
+            .create_external_texture(raw_gl_texture, target, (1, 1))?;
+
+        let pso = match target {
+            graphics::ExternalTextureTarget::Texture2D => self.pso,
+            graphics::ExternalTextureTarget::ExternalOES => self.external_pso,
+        };
+
+        self.submit_with_pipeline(verts, idxes, texture, pso)?;
+        self.flush()?;
+
+        self.video.delete_texture(texture);
+        Ok(())
+    }
+
+    fn submit_with_pipeline(&mut self,
+                             verts: &[CanvasVertex],
+                             idxes: &[u16],
+                             texture: graphics::TextureHandle,
+                             pso: graphics::PipelineStateHandle)
+                             -> Result<()> {
+        self.current_pso = pso;
+        self.submit(verts, idxes, texture)
+    }
+
     pub fn flush(&mut self) -> Result<()> {
         if self.idxes.len() <= 0 {
             return Ok(());
@@ -150,12 +207,13 @@ impl CanvasRenderer {
         }
 
         dc.with_view(self.vso)
-            .with_pipeline(self.pso)
+            .with_pipeline(self.current_pso)
             .with_data(self.vbo, Some(self.ibo))
             .submit(graphics::Primitive::Triangles, 0, self.idxes.len() as u32)?;
 
         self.verts.clear();
         self.idxes.clear();
+        self.current_pso = self.pso;
         Ok(())
     }
 }
@@ -166,5 +224,6 @@ impl Drop for CanvasRenderer {
         self.video.delete_index_buffer(self.ibo);
         self.video.delete_view(self.vso);
         self.video.delete_pipeline(self.pso);
+        self.video.delete_pipeline(self.external_pso);
     }
 }
\ No newline at end of file
@@ -34,6 +34,10 @@ pub struct FrameInfo {
     pub video: graphics::GraphicsFrameInfo,
     pub duration: time::Duration,
     pub fps: u32,
+    /// Bytes reclaimed by `Engine`'s per-frame `utils::FrameAllocator` when it
+    /// was reset ahead of this frame, so users can watch transient-allocation
+    /// churn from `Application::on_post_update`.
+    pub reclaimed_bytes: usize,
 }
 
 /// `Application` is a user-friendly facade to building application, which defines a number
@@ -51,6 +55,12 @@ pub trait Application {
     }
 
     /// `Application::on_post_update` is called after camera has rendered the scene.
+    ///
+    /// By this point `Engine` has already reset its per-frame `FrameAllocator`
+    /// for the next frame and folded the reclaimed byte count into
+    /// `FrameInfo::reclaimed_bytes`, so every transient allocation made
+    /// during `on_update`/`on_render` is guaranteed to have been reclaimed
+    /// before this call.
     fn on_post_update(&mut self, _: &Context, _: &FrameInfo) -> Result<()> {
         Ok(())
     }
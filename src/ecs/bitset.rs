@@ -1,15 +1,22 @@
 use std::borrow::Borrow;
 
+/// Number of `u64` words backing `BitSet` when no other capacity is named
+/// explicitly, i.e. today's 64-index cap.
 const MAX_COMPONENTS: usize = 1;
 
+/// A fixed-capacity, inline bitset of `N` words (`N * 64` indices), with no
+/// heap allocation. `N` defaults to `MAX_COMPONENTS`, so existing callers
+/// that just write `BitSet` keep today's 64-index capacity; a caller whose
+/// component/entity id space is wider picks a bigger one explicitly with
+/// `BitSet<4>` and so on. See `DynamicBitSet` for the unbounded case.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct BitSet {
-    bits: [u64; MAX_COMPONENTS],
+pub struct BitSet<const N: usize = MAX_COMPONENTS> {
+    bits: [u64; N],
 }
 
-impl BitSet {
+impl<const N: usize> BitSet<N> {
     pub fn new() -> Self {
-        BitSet { bits: [0; MAX_COMPONENTS] }
+        BitSet { bits: [0; N] }
     }
 
     #[inline(always)]
@@ -39,50 +46,127 @@ impl BitSet {
     pub fn intersect_with<T>(&self, rhs: T) -> Self
         where T: Borrow<Self>
     {
-        let mut bs = BitSet::new();
+        let mut bs = Self::new();
         let rhs = rhs.borrow();
-        for i in 0..MAX_COMPONENTS {
+        for i in 0..N {
             bs.bits[i] = self.bits[i] & rhs.bits[i];
         }
         bs
     }
 
+    /// Returns the bitwise union, `self | rhs`.
     #[inline(always)]
-    pub fn iter(&self) -> BitSetIter {
+    pub fn union_with<T>(&self, rhs: T) -> Self
+        where T: Borrow<Self>
+    {
+        let mut bs = Self::new();
+        let rhs = rhs.borrow();
+        for i in 0..N {
+            bs.bits[i] = self.bits[i] | rhs.bits[i];
+        }
+        bs
+    }
+
+    /// Returns the set difference, `self & !rhs` -- every index in `self`
+    /// that is not also in `rhs`.
+    #[inline(always)]
+    pub fn difference_with<T>(&self, rhs: T) -> Self
+        where T: Borrow<Self>
+    {
+        let mut bs = Self::new();
+        let rhs = rhs.borrow();
+        for i in 0..N {
+            bs.bits[i] = self.bits[i] & !rhs.bits[i];
+        }
+        bs
+    }
+
+    /// Returns the symmetric difference, `self ^ rhs` -- every index present
+    /// in exactly one of `self`/`rhs`.
+    #[inline(always)]
+    pub fn symmetric_difference_with<T>(&self, rhs: T) -> Self
+        where T: Borrow<Self>
+    {
+        let mut bs = Self::new();
+        let rhs = rhs.borrow();
+        for i in 0..N {
+            bs.bits[i] = self.bits[i] ^ rhs.bits[i];
+        }
+        bs
+    }
+
+    /// Returns the number of indices currently set.
+    #[inline(always)]
+    pub fn count(&self) -> u32 {
+        self.bits.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// Returns `true` if no index is set.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&w| w == 0)
+    }
+
+    /// Returns `true` if every index set in `self` is also set in `rhs`.
+    #[inline(always)]
+    pub fn is_subset<T>(&self, rhs: T) -> bool
+        where T: Borrow<Self>
+    {
+        let rhs = rhs.borrow();
+        self.intersect_with(rhs) == *self
+    }
+
+    /// Returns `true` if every index set in `rhs` is also set in `self`.
+    #[inline(always)]
+    pub fn is_superset<T>(&self, rhs: T) -> bool
+        where T: Borrow<Self>
+    {
+        rhs.borrow().is_subset(self)
+    }
+
+    #[inline(always)]
+    pub fn iter(&self) -> BitSetIter<N> {
+        let w = self.bits[0];
         BitSetIter {
             bitset: *self,
-            cursor: 0,
+            word: 0,
+            residual: w,
         }
     }
 
     #[inline(always)]
     fn split(index: usize) -> (usize, usize) {
-        let len = MAX_COMPONENTS * 64;
-        assert!(index < len,
-                "Too many components. (MAX_COMPONENTS: {:?})",
-                len);
-        (index / len, index % len)
+        let len = N * 64;
+        assert!(index < len, "Too many components. (capacity: {:?})", len);
+        (index / 64, index % 64)
     }
 }
 
-pub struct BitSetIter {
-    bitset: BitSet,
-    cursor: usize,
+/// Walks only the set bits, at a cost proportional to their count rather
+/// than to the bitset's full capacity: `residual` holds the not-yet-yielded
+/// bits of the current word, and each call clears the lowest set bit of it
+/// with `w &= w - 1` instead of testing every index in turn.
+pub struct BitSetIter<const N: usize = MAX_COMPONENTS> {
+    bitset: BitSet<N>,
+    word: usize,
+    residual: u64,
 }
 
-impl Iterator for BitSetIter {
+impl<const N: usize> Iterator for BitSetIter<N> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.cursor < MAX_COMPONENTS * 64 {
-            self.cursor += 1;
-
-            if self.bitset.contains(self.cursor - 1) {
-                return Some(self.cursor - 1);
+        while self.residual == 0 {
+            self.word += 1;
+            if self.word >= N {
+                return None;
             }
+            self.residual = self.bitset.bits[self.word];
         }
 
-        None
+        let t = self.residual.trailing_zeros();
+        self.residual &= self.residual - 1;
+        Some(self.word * 64 + t as usize)
     }
 }
 
@@ -138,11 +222,84 @@ impl DynamicBitSet {
         self.bits.clear();
     }
 
+    /// Returns the bitwise union, `self | rhs`.
+    pub fn union_with<T>(&self, rhs: T) -> Self
+        where T: Borrow<Self>
+    {
+        let rhs = rhs.borrow();
+        let len = self.bits.len().max(rhs.bits.len());
+        let mut bits = Vec::with_capacity(len);
+        for i in 0..len {
+            let a = self.bits.get(i).cloned().unwrap_or(0);
+            let b = rhs.bits.get(i).cloned().unwrap_or(0);
+            bits.push(a | b);
+        }
+        DynamicBitSet { bits: bits }
+    }
+
+    /// Returns the set difference, `self & !rhs`.
+    pub fn difference_with<T>(&self, rhs: T) -> Self
+        where T: Borrow<Self>
+    {
+        let rhs = rhs.borrow();
+        let mut bits = Vec::with_capacity(self.bits.len());
+        for i in 0..self.bits.len() {
+            let b = rhs.bits.get(i).cloned().unwrap_or(0);
+            bits.push(self.bits[i] & !b);
+        }
+        DynamicBitSet { bits: bits }
+    }
+
+    /// Returns the symmetric difference, `self ^ rhs`.
+    pub fn symmetric_difference_with<T>(&self, rhs: T) -> Self
+        where T: Borrow<Self>
+    {
+        let rhs = rhs.borrow();
+        let len = self.bits.len().max(rhs.bits.len());
+        let mut bits = Vec::with_capacity(len);
+        for i in 0..len {
+            let a = self.bits.get(i).cloned().unwrap_or(0);
+            let b = rhs.bits.get(i).cloned().unwrap_or(0);
+            bits.push(a ^ b);
+        }
+        DynamicBitSet { bits: bits }
+    }
+
+    /// Returns the number of indices currently set.
+    pub fn count(&self) -> u32 {
+        self.bits.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// Returns `true` if no index is set.
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&w| w == 0)
+    }
+
+    /// Returns `true` if every index set in `self` is also set in `rhs`.
+    pub fn is_subset<T>(&self, rhs: T) -> bool
+        where T: Borrow<Self>
+    {
+        let rhs = rhs.borrow();
+        self.bits
+            .iter()
+            .enumerate()
+            .all(|(i, &w)| w & !rhs.bits.get(i).cloned().unwrap_or(0) == 0)
+    }
+
+    /// Returns `true` if every index set in `rhs` is also set in `self`.
+    pub fn is_superset<T>(&self, rhs: T) -> bool
+        where T: Borrow<Self>
+    {
+        rhs.borrow().is_subset(self)
+    }
+
     #[inline(always)]
     pub fn iter(&self) -> DynamicBitSetIter {
+        let w = self.bits.get(0).cloned().unwrap_or(0);
         DynamicBitSetIter {
             bitset: self,
-            cursor: 0,
+            word: 0,
+            residual: w,
         }
     }
 
@@ -155,24 +312,25 @@ impl DynamicBitSet {
 
 pub struct DynamicBitSetIter<'a> {
     bitset: &'a DynamicBitSet,
-    cursor: usize,
+    word: usize,
+    residual: u64,
 }
 
 impl<'a> Iterator for DynamicBitSetIter<'a> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let len = self.bitset.bits.len() * 64;
-
-        while self.cursor < len {
-            self.cursor += 1;
-
-            if self.bitset.contains(self.cursor - 1) {
-                return Some(self.cursor - 1);
+        while self.residual == 0 {
+            self.word += 1;
+            if self.word >= self.bitset.bits.len() {
+                return None;
             }
+            self.residual = self.bitset.bits[self.word];
         }
 
-        None
+        let t = self.residual.trailing_zeros();
+        self.residual &= self.residual - 1;
+        Some(self.word * 64 + t as usize)
     }
 }
 
@@ -238,4 +396,64 @@ mod test {
         assert!(!v.contains(9));
         assert!(!v.contains(10));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn iter_yields_only_set_bits() {
+        let mut bits = BitSet::new();
+        bits.insert(1);
+        bits.insert(3);
+        bits.insert(9);
+
+        let v: Vec<_> = bits.iter().collect();
+        assert_eq!(v, vec![1, 3, 9]);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let mut lhs = BitSet::new();
+        lhs.insert(1);
+        lhs.insert(3);
+
+        let mut rhs = BitSet::new();
+        rhs.insert(3);
+        rhs.insert(5);
+
+        assert_eq!(lhs.union_with(&rhs).iter().collect::<Vec<_>>(),
+                   vec![1, 3, 5]);
+        assert_eq!(lhs.difference_with(&rhs).iter().collect::<Vec<_>>(),
+                   vec![1]);
+        assert_eq!(lhs.symmetric_difference_with(&rhs).iter().collect::<Vec<_>>(),
+                   vec![1, 5]);
+        assert_eq!(lhs.count(), 2);
+        assert!(!lhs.is_empty());
+        assert!(BitSet::new().is_empty());
+
+        let mut subset = BitSet::new();
+        subset.insert(3);
+        assert!(subset.is_subset(&lhs));
+        assert!(lhs.is_superset(&subset));
+        assert!(!lhs.is_subset(&subset));
+    }
+
+    #[test]
+    fn cross_word_boundary() {
+        let mut bits = BitSet::<2>::new();
+
+        bits.insert(63);
+        bits.insert(64);
+        bits.insert(127);
+
+        assert!(bits.contains(63));
+        assert!(bits.contains(64));
+        assert!(bits.contains(127));
+        assert!(!bits.contains(62));
+        assert!(!bits.contains(65));
+
+        assert_eq!(bits.iter().collect::<Vec<_>>(), vec![63, 64, 127]);
+        assert_eq!(bits.count(), 3);
+
+        bits.remove(64);
+        assert!(!bits.contains(64));
+        assert_eq!(bits.iter().collect::<Vec<_>>(), vec![63, 127]);
+    }
+}
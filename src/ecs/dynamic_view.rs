@@ -0,0 +1,157 @@
+//! Runtime component queries, for callers that only know which `Component`
+//! types they want as data (editors, scripting layers) rather than at
+//! compile time.
+//!
+//! `ecs::iterator`'s `build_view_with!` macro only ever generates views over a
+//! statically-known component list. `DynamicView` instead takes a runtime
+//! `Vec<TypeId>`, intersects `World`'s own per-component occupancy
+//! bitmasks, and yields the matching `Entity`s along with a type-erased
+//! accessor for each. `DynamicViewOne` is the single-component fast path --
+//! no mask intersection needed, it just walks that component's own
+//! occupancy mask. Both share `ecs::iterator`'s `BorrowGuard` machinery, so
+//! a `DynamicView` and a conflicting `build_view_with!` view over the same
+//! component still panic instead of racing.
+
+use std::any::{Any, TypeId};
+
+use super::bitset::BitSet;
+use super::iterator::BorrowGuard;
+use super::{Entity, World};
+use utils::HandleIter;
+
+/// Type-erased accessor for one component arena, registered with `World` so
+/// `DynamicView` can fetch components without knowing their concrete type.
+pub trait DynamicArena: Any {
+    fn get_dyn(&self, entity: Entity) -> Option<&Any>;
+    fn get_dyn_mut(&mut self, entity: Entity) -> Option<&mut Any>;
+}
+
+/// A runtime-assembled query over `World`, built from a `Vec<TypeId>`
+/// instead of a compile-time component list.
+pub struct DynamicView<'a> {
+    world: &'a World,
+    type_ids: Vec<TypeId>,
+    mask: BitSet,
+    /// Index, into `type_ids`, of the component whose occupancy mask has
+    /// the fewest set bits -- the one `iter` walks, since it can only ever
+    /// shrink the result set, never grow it.
+    smallest: usize,
+    _borrows: Vec<BorrowGuard>,
+}
+
+impl<'a> DynamicView<'a> {
+    /// Builds a view over every `Entity` carrying all of `type_ids`.
+    ///
+    /// Panics if `type_ids` is empty, if any of them was never registered
+    /// with `World`, or if one of their arenas is already exclusively
+    /// borrowed by another live view.
+    pub fn new(world: &'a World, type_ids: Vec<TypeId>) -> Self {
+        assert!(!type_ids.is_empty(), "DynamicView requires at least one component type.");
+
+        let mut mask = BitSet::new();
+        let mut borrows = Vec::with_capacity(type_ids.len());
+        let mut smallest = 0;
+        let mut smallest_count = None;
+
+        for (i, type_id) in type_ids.iter().enumerate() {
+            let index = world.dynamic_arena_index(*type_id)
+                .expect("DynamicView built from an unregistered component TypeId.");
+            mask.insert(index);
+            borrows.push(BorrowGuard::exclusive_for(*type_id));
+
+            let count = world.dynamic_arena_len(*type_id);
+            if smallest_count.map(|c| count < c).unwrap_or(true) {
+                smallest_count = Some(count);
+                smallest = i;
+            }
+        }
+
+        DynamicView {
+            world: world,
+            type_ids: type_ids,
+            mask: mask,
+            smallest: smallest,
+            _borrows: borrows,
+        }
+    }
+
+    /// Iterates every `Entity` carrying all of this view's component types,
+    /// scanning the narrowest one's occupancy mask first.
+    pub fn iter(&self) -> DynamicViewIter {
+        DynamicViewIter {
+            view: self,
+            iterator: self.world.dynamic_arena_iter(self.type_ids[self.smallest]),
+        }
+    }
+
+    /// Type-erased immutable fetch of `type_id`'s component on `entity`.
+    pub fn get(&self, entity: Entity, type_id: TypeId) -> Option<&Any> {
+        self.world.dynamic_arena(type_id).and_then(|arena| arena.get_dyn(entity))
+    }
+
+    /// Type-erased mutable fetch of `type_id`'s component on `entity`.
+    pub fn get_mut(&self, entity: Entity, type_id: TypeId) -> Option<&mut Any> {
+        self.world.dynamic_arena_mut(type_id).and_then(|arena| arena.get_dyn_mut(entity))
+    }
+}
+
+pub struct DynamicViewIter<'a> {
+    view: &'a DynamicView<'a>,
+    iterator: HandleIter<'a>,
+}
+
+impl<'a> Iterator for DynamicViewIter<'a> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        loop {
+            match self.iterator.next() {
+                Some(ent) => {
+                    let mask = unsafe {
+                        self.view.world.masks.get_unchecked(ent.index() as usize).clone()
+                    };
+
+                    if mask.intersect_with(&self.view.mask) == self.view.mask {
+                        return Some(ent);
+                    }
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+/// Single-component fast path for `DynamicView` -- no mask intersection
+/// needed, so it just walks the component's own occupancy mask directly.
+pub struct DynamicViewOne<'a> {
+    world: &'a World,
+    type_id: TypeId,
+    _borrow: BorrowGuard,
+}
+
+impl<'a> DynamicViewOne<'a> {
+    /// Panics if `type_id` was never registered with `World`, or if its
+    /// arena is already exclusively borrowed by another live view.
+    pub fn new(world: &'a World, type_id: TypeId) -> Self {
+        world.dynamic_arena_index(type_id)
+            .expect("DynamicViewOne built from an unregistered component TypeId.");
+
+        DynamicViewOne {
+            world: world,
+            type_id: type_id,
+            _borrow: BorrowGuard::exclusive_for(type_id),
+        }
+    }
+
+    pub fn iter(&self) -> HandleIter {
+        self.world.dynamic_arena_iter(self.type_id)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&Any> {
+        self.world.dynamic_arena(self.type_id).and_then(|arena| arena.get_dyn(entity))
+    }
+
+    pub fn get_mut(&self, entity: Entity) -> Option<&mut Any> {
+        self.world.dynamic_arena_mut(self.type_id).and_then(|arena| arena.get_dyn_mut(entity))
+    }
+}
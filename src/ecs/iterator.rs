@@ -1,34 +1,125 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once};
+
+const UNUSED: isize = 0;
+const WRITING: isize = -1;
+
+/// Runtime borrow tracker for a single component arena, keyed by `TypeId` so
+/// every `build_view_with!` view over the same component shares one flag.
+/// Mirrors `RefCell`'s `UNUSED`/shared/exclusive states; views only ever take
+/// an exclusive (write) borrow, so a second, overlapping view over the same
+/// arena panics instead of racing the first one.
+struct BorrowFlag(AtomicIsize);
+
+/// Process-global (not thread-local) so two views over the same component
+/// built from different OS threads -- e.g. two `Dispatcher` wave workers --
+/// still see each other's borrow, instead of each getting its own empty
+/// thread-local map and both succeeding.
+fn borrows() -> &'static Mutex<HashMap<TypeId, Arc<BorrowFlag>>> {
+    static INIT: Once = Once::new();
+    static mut BORROWS: *const Mutex<HashMap<TypeId, Arc<BorrowFlag>>> = 0 as *const _;
+
+    unsafe {
+        INIT.call_once(|| {
+            BORROWS = Box::into_raw(Box::new(Mutex::new(HashMap::new())));
+        });
+        &*BORROWS
+    }
+}
+
+fn flag_for(type_id: TypeId) -> Arc<BorrowFlag> {
+    let mut borrows = borrows().lock().unwrap();
+    borrows.entry(type_id)
+        .or_insert_with(|| Arc::new(BorrowFlag(AtomicIsize::new(UNUSED))))
+        .clone()
+}
+
+/// Releases its arena's exclusive borrow once every clone of this guard (one
+/// lives in a `View`, more are handed out to each `ViewSlice` split from it)
+/// has been dropped. `Arc`+atomics rather than `Rc`+`Cell`, since a
+/// `ViewSlice` (and the guard it carries) can be split across rayon worker
+/// threads and cloned/dropped concurrently with the original.
+pub struct BorrowGuard {
+    flag: Arc<BorrowFlag>,
+    refs: Arc<AtomicUsize>,
+}
+
+impl BorrowGuard {
+    /// Panics if the arena for `C` is already borrowed by another live view.
+    pub fn exclusive<C: 'static>() -> Self {
+        Self::exclusive_for(TypeId::of::<C>())
+    }
+
+    /// `TypeId`-keyed counterpart of `exclusive`, for callers (like
+    /// `ecs::dynamic_view`'s runtime views) that only have a `TypeId` in
+    /// hand, not a concrete component type to turbofish.
+    pub fn exclusive_for(type_id: TypeId) -> Self {
+        let flag = flag_for(type_id);
+        if flag.0.compare_exchange(UNUSED, WRITING, Ordering::SeqCst, Ordering::SeqCst) != Ok(UNUSED) {
+            panic!("component arena already borrowed by an overlapping view");
+        }
+        BorrowGuard {
+            flag: flag,
+            refs: Arc::new(AtomicUsize::new(1)),
+        }
+    }
+}
+
+impl Clone for BorrowGuard {
+    fn clone(&self) -> Self {
+        self.refs.fetch_add(1, Ordering::SeqCst);
+        BorrowGuard {
+            flag: self.flag.clone(),
+            refs: self.refs.clone(),
+        }
+    }
+}
+
+impl Drop for BorrowGuard {
+    fn drop(&mut self) {
+        if self.refs.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.flag.0.store(UNUSED, Ordering::SeqCst);
+        }
+    }
+}
+
 macro_rules! build_view_with {
     ($name: ident[$($cps: ident), *]) => (
 
         mod $name {
             use $crate::ecs::bitset::BitSet;
             use $crate::ecs::*;
+            use $crate::ecs::iterator::BorrowGuard;
             use $crate::ecs::world::ArenaWriteGuard;
             use $crate::utils::HandleIter;
 
-            pub struct View<'a> {
+            pub struct View<'a, $($cps: Component), *> {
                 world: &'a World,
                 mask: BitSet,
+                guards: ($(ArenaWriteGuard<'a, $cps>,) *),
+                _borrows: ($(BorrowGuard,) *),
             }
 
-            impl<'a> IntoIterator for View<'a> {
-                type Item = Entity;
-                type IntoIter = ViewIterator<'a>;
+            impl<'a, $($cps: Component), *> IntoIterator for View<'a, $($cps), *> {
+                type Item = (Entity, $(&'a mut $cps), *);
+                type IntoIter = ViewIterator<'a, $($cps), *>;
 
-                fn into_iter(self) -> ViewIterator<'a> {
+                fn into_iter(self) -> ViewIterator<'a, $($cps), *> {
                     let iter = self.world.iter();
                     ViewIterator { view: self, iterator: iter }
                 }
             }
 
-            pub struct ViewIterator<'a> {
-                view: View<'a>,
+            pub struct ViewIterator<'a, $($cps: Component), *> {
+                view: View<'a, $($cps), *>,
                 iterator: HandleIter<'a>,
             }
 
-            fn next_item<'a>(view: &View<'a>,
-                             iterator: &mut HandleIter<'a>) -> Option<Entity>
+            fn next_entity<'a, $($cps: Component), *>(view: &View<'a, $($cps), *>,
+                                                       iterator: &mut HandleIter<'a>)
+                                                       -> Option<Entity>
             {
                 loop {
                     match iterator.next() {
@@ -48,75 +139,111 @@ macro_rules! build_view_with {
                 }
             }
 
-            impl<'a> Iterator for ViewIterator<'a> {
-                type Item = Entity;
+            // Unsafe because the returned references are tied to `'a`, the
+            // lifetime of the arenas backing `view.guards`, not to `view`'s
+            // own borrow -- callers must not alias the same `Entity` twice.
+            unsafe fn fetch<'a, $($cps: Component), *>(view: &mut View<'a, $($cps), *>,
+                                                        ent: Entity)
+                                                        -> (Entity, $(&'a mut $cps), *) {
+                let ($(ref mut $cps,) *) = view.guards;
+                (ent, $($cps.get_unchecked_mut(ent),) *)
+            }
+
+            impl<'a, $($cps: Component), *> Iterator for ViewIterator<'a, $($cps), *> {
+                type Item = (Entity, $(&'a mut $cps), *);
 
                 fn next(&mut self) -> Option<Self::Item> {
                     unsafe {
                         let iter = &mut self.iterator as *mut HandleIter;
-                        next_item(&self.view, &mut *iter)
+                        let view = &mut self.view as *mut View<$($cps), *>;
+                        next_entity(&*view, &mut *iter).map(|ent| fetch(&mut *view, ent))
                     }
                 }
             }
 
-            impl<'a> View<'a> {
-                pub fn as_slice(&mut self) -> ViewSlice {
+            impl<'a, $($cps: Component), *> View<'a, $($cps), *> {
+                pub fn as_slice(&mut self) -> ViewSlice<$($cps), *> {
                     let iter = self.world.iter();
                     ViewSlice {
-                        view: self as *mut View as * mut (),
+                        view: self as *mut View<$($cps), *> as * mut (),
                         iterator: iter,
+                        borrows: self._borrows.clone(),
+                        _marker: ::std::marker::PhantomData,
                     }
                 }
             }
 
-            pub struct ViewSlice<'a> {
+            pub struct ViewSlice<'a, $($cps: Component), *> {
                 view: *mut (),
                 iterator: HandleIter<'a>,
+                borrows: ($(BorrowGuard,) *),
+                _marker: ::std::marker::PhantomData<($($cps,) *)>,
             }
 
-            impl<'a> Iterator for ViewSlice<'a> {
-                type Item = Entity;
+            impl<'a, $($cps: Component), *> Iterator for ViewSlice<'a, $($cps), *> {
+                type Item = (Entity, $(&'a mut $cps), *);
 
                 fn next(&mut self) -> Option<Self::Item> {
                     unsafe {
                         let iter = &mut self.iterator as *mut HandleIter;
-                        let view = &mut *(self.view as *mut View);
-                        next_item(view, &mut *iter)
+                        let view = &mut *(self.view as *mut View<$($cps), *>);
+                        next_entity(view, &mut *iter).map(|ent| fetch(view, ent))
                     }
                 }
             }
 
-            unsafe impl<'a> Send for ViewSlice<'a> {}
-            unsafe impl<'a> Sync for ViewSlice<'a> {}
+            unsafe impl<'a, $($cps: Component), *> Send for ViewSlice<'a, $($cps), *> {}
+            unsafe impl<'a, $($cps: Component), *> Sync for ViewSlice<'a, $($cps), *> {}
 
-            impl<'a> ViewSlice<'a> {
-                pub fn split_with(&mut self, len: usize) -> (ViewSlice, ViewSlice) {
+            impl<'a, $($cps: Component), *> ViewSlice<'a, $($cps), *> {
+                pub fn split_with(&mut self,
+                                  len: usize)
+                                  -> (ViewSlice<$($cps), *>, ViewSlice<$($cps), *>) {
                     let (lhs, rhs) = self.iterator.split_with(len);
-                    (ViewSlice { view: self.view, iterator: lhs },
-                     ViewSlice { view: self.view, iterator: rhs })
+                    (ViewSlice {
+                         view: self.view,
+                         iterator: lhs,
+                         borrows: self.borrows.clone(),
+                         _marker: ::std::marker::PhantomData,
+                     },
+                     ViewSlice {
+                         view: self.view,
+                         iterator: rhs,
+                         borrows: self.borrows.clone(),
+                         _marker: ::std::marker::PhantomData,
+                     })
                 }
 
-                pub fn split(&mut self) -> (ViewSlice, ViewSlice) {
+                pub fn split(&mut self) -> (ViewSlice<$($cps), *>, ViewSlice<$($cps), *>) {
                     let (lhs, rhs) = self.iterator.split();
-                    (ViewSlice { view: self.view, iterator: lhs },
-                     ViewSlice { view: self.view, iterator: rhs } )
+                    (ViewSlice {
+                         view: self.view,
+                         iterator: lhs,
+                         borrows: self.borrows.clone(),
+                         _marker: ::std::marker::PhantomData,
+                     },
+                     ViewSlice {
+                         view: self.view,
+                         iterator: rhs,
+                         borrows: self.borrows.clone(),
+                         _marker: ::std::marker::PhantomData,
+                     })
                 }
             }
 
             impl World {
-                pub fn $name<$($cps), *>(&self) -> (View, ($(ArenaWriteGuard<$cps>), *))
-                    where $($cps:Component, )*
+                pub fn $name<$($cps), *>(&self) -> View<$($cps), *>
+                    where $($cps: Component, )*
                 {
                     let mut mask = BitSet::new();
                     $( mask.insert(self.arena_index::<$cps>()); ) *
 
-                    (
-                        View {
-                            world: self,
-                            mask: mask,
-                        },
-                        ( $(self.arena_mut::<$cps>()), * )
-                    )
+                    View {
+                        world: self,
+                        mask: mask,
+                        guards: ( $(self.arena_mut::<$cps>(),) * ),
+                        _borrows: ( $(BorrowGuard::exclusive::<$cps>(),) * ),
+                    }
                 }
             }
         }
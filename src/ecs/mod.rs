@@ -36,9 +36,13 @@ mod iterator;
 #[macro_use]
 pub mod component;
 pub mod world;
+pub mod scheduler;
+pub mod dynamic_view;
 
 pub use self::component::{Component, ComponentArena, HashMapArena, VecArena};
 pub use self::world::{World, Arena, ArenaMut};
+pub use self::scheduler::{Access, Dispatcher, DispatcherBuilder, System};
+pub use self::dynamic_view::{DynamicArena, DynamicView, DynamicViewOne};
 
 /// `Entity` type, as seen by the user, its a alias to `Handle` internally.
 pub type Entity = Handle;
\ No newline at end of file
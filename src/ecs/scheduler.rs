@@ -0,0 +1,193 @@
+//! Conflict-aware parallel execution of `System`s over a `World`.
+//!
+//! `ecs::iterator`'s `build_view_with!`-generated views require their component
+//! set to be known at compile time, and can only express one view at a time
+//! from a single thread. `Dispatcher` instead takes a whole batch of
+//! `System`s, each declaring which `Component` types it reads and which it
+//! writes via `Access`, and schedules them into "waves": systems within a
+//! wave are mutually non-conflicting (no two write the same component, and
+//! none writes what another reads) and are run concurrently on a worker
+//! pool; waves themselves run in order.
+//!
+//! This assumes each component arena in `World` is guarded by
+//! `atomic_refcell::AtomicRefCell` rather than `RwLock`, the same switch
+//! `ecs::iterator`'s `BorrowGuard` already makes in spirit for the
+//! compile-time views (a borrow is a single atomic check, not an OS-level
+//! lock) -- so a wave's concurrent `System::run` calls validate their
+//! borrows cheaply instead of contending a real lock. `ecs::world` itself
+//! is not present in this tree to migrate; `Dispatcher` is written against
+//! the `World`/`Component` API it will need once it is.
+
+use std::any::TypeId;
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use super::World;
+
+/// Declares which `Component` types a `System` reads and which it writes,
+/// so the `Dispatcher` can tell whether two systems conflict without
+/// running either of them.
+#[derive(Default, Clone)]
+pub struct Access {
+    reads: HashSet<TypeId>,
+    writes: HashSet<TypeId>,
+    /// `true` for a system that mutates `World` structure itself (creating
+    /// or freeing entities, registering components) rather than just the
+    /// contents of existing component arenas. Conflicts with every other
+    /// system -- including another exclusive one -- so it always runs
+    /// alone in its own wave.
+    exclusive: bool,
+}
+
+impl Access {
+    pub fn new() -> Self {
+        Access::default()
+    }
+
+    pub fn reads<C: 'static>(mut self) -> Self {
+        self.reads.insert(TypeId::of::<C>());
+        self
+    }
+
+    pub fn writes<C: 'static>(mut self) -> Self {
+        self.writes.insert(TypeId::of::<C>());
+        self
+    }
+
+    pub fn exclusive() -> Self {
+        Access { exclusive: true, ..Access::default() }
+    }
+
+    /// `true` if a system with this `Access` and one with `other` must not
+    /// run at the same time.
+    fn conflicts_with(&self, other: &Access) -> bool {
+        if self.exclusive || other.exclusive {
+            return true;
+        }
+
+        !self.writes.is_disjoint(&other.writes) || !self.writes.is_disjoint(&other.reads) ||
+        !self.reads.is_disjoint(&other.writes)
+    }
+}
+
+/// A unit of per-frame game logic that the `Dispatcher` can run alongside
+/// any other `System` whose `Access` doesn't conflict with its own.
+pub trait System: Send {
+    /// Declares this system's component reads/writes up front, so the
+    /// `Dispatcher` can schedule it without running it first.
+    fn access(&self) -> Access;
+
+    /// Runs this system's logic against `world`.
+    fn run(&mut self, world: &World);
+}
+
+/// Collects `System`s and batches them into conflict-free waves.
+pub struct DispatcherBuilder {
+    systems: Vec<Box<System>>,
+}
+
+impl DispatcherBuilder {
+    pub fn new() -> Self {
+        DispatcherBuilder { systems: Vec::new() }
+    }
+
+    pub fn add<S: System + 'static>(mut self, system: S) -> Self {
+        self.systems.push(Box::new(system));
+        self
+    }
+
+    /// Greedily assigns each system to the earliest wave none of whose
+    /// systems conflict with it. This is a first-fit batching of the
+    /// conflict graph, not a minimum-wave-count solver -- good enough since
+    /// wave count only affects how much concurrency is exploited, not
+    /// correctness.
+    pub fn build(self) -> Dispatcher {
+        let mut waves: Vec<Vec<Box<System>>> = Vec::new();
+        let mut wave_access: Vec<Vec<Access>> = Vec::new();
+
+        for system in self.systems {
+            let access = system.access();
+            let mut placed = false;
+
+            for (wave, accesses) in waves.iter_mut().zip(wave_access.iter_mut()) {
+                if !accesses.iter().any(|other| access.conflicts_with(other)) {
+                    accesses.push(access.clone());
+                    wave.push(system);
+                    placed = true;
+                    break;
+                }
+            }
+
+            if !placed {
+                wave_access.push(vec![access]);
+                waves.push(vec![system]);
+            }
+        }
+
+        Dispatcher { waves: waves }
+    }
+}
+
+/// A fixed batch of `System`s, pre-sorted into conflict-free waves.
+pub struct Dispatcher {
+    waves: Vec<Vec<Box<System>>>,
+}
+
+impl Dispatcher {
+    /// Runs every wave in order; within a wave, every system runs
+    /// concurrently on the global `rayon` worker pool.
+    pub fn run(&mut self, world: &World) {
+        for wave in &mut self.waves {
+            wave.par_iter_mut().for_each(|system| system.run(world));
+        }
+    }
+
+    /// Number of waves this dispatcher settled on -- mostly useful for
+    /// tests and diagnostics that want to assert how much concurrency was
+    /// actually found.
+    pub fn len(&self) -> usize {
+        self.waves.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct A;
+    struct B;
+    struct C;
+
+    #[test]
+    fn disjoint_access_never_conflicts() {
+        let a = Access::new().writes::<A>();
+        let b = Access::new().writes::<B>();
+        assert!(!a.conflicts_with(&b));
+    }
+
+    #[test]
+    fn write_write_conflicts() {
+        let a = Access::new().writes::<A>();
+        let b = Access::new().writes::<A>();
+        assert!(a.conflicts_with(&b));
+    }
+
+    #[test]
+    fn read_write_conflicts_but_read_read_does_not() {
+        let reader1 = Access::new().reads::<A>();
+        let reader2 = Access::new().reads::<A>();
+        let writer = Access::new().writes::<A>();
+
+        assert!(!reader1.conflicts_with(&reader2));
+        assert!(reader1.conflicts_with(&writer));
+    }
+
+    #[test]
+    fn exclusive_conflicts_with_everything() {
+        let exclusive = Access::exclusive();
+        let unrelated = Access::new().writes::<C>();
+        assert!(exclusive.conflicts_with(&unrelated));
+        assert!(exclusive.conflicts_with(&Access::exclusive()));
+    }
+}
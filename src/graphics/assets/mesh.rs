@@ -1,6 +1,7 @@
 //! Immutable or dynamic vertex and index data.
 
 use graphics::MAX_VERTEX_ATTRIBUTES;
+use graphics::errors::*;
 
 /// Hint abouts the intended update strategy of the data.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -64,6 +65,9 @@ pub struct IndexBufferSetup {
     pub num: u32,
     /// The format.
     pub format: IndexFormat,
+    /// When `true` and `create_index_buffer` is called without initial data,
+    /// the buffer's contents are zeroed on creation instead of left undefined.
+    pub zero_init: bool,
 }
 
 impl Default for IndexBufferSetup {
@@ -72,6 +76,7 @@ impl Default for IndexBufferSetup {
             hint: BufferHint::Immutable,
             num: 0,
             format: IndexFormat::U16,
+            zero_init: false,
         }
     }
 }
@@ -89,6 +94,9 @@ pub struct VertexBufferSetup {
     pub hint: BufferHint,
     pub layout: VertexLayout,
     pub num: u32,
+    /// When `true` and `create_vertex_buffer` is called without initial data,
+    /// the buffer's contents are zeroed on creation instead of left undefined.
+    pub zero_init: bool,
 }
 
 impl VertexBufferSetup {
@@ -104,6 +112,7 @@ impl Default for VertexBufferSetup {
             hint: BufferHint::Immutable,
             layout: VertexLayout::default(),
             num: 0,
+            zero_init: false,
         }
     }
 }
@@ -142,7 +151,18 @@ pub enum VertexFormat {
     UByte,
     Short,
     UShort,
+    /// 16-bit (half-precision) float, one per component. Half the size of
+    /// `Float` at the cost of precision/range -- a common trade for
+    /// attributes like `Texcoord*` that don't need full `f32` precision.
+    Half,
     Float,
+    /// Four components (x, y, z, w) packed into a single signed 32-bit word
+    /// as 10/10/10/2 bits respectively. Always declared with `size == 4`.
+    /// Suited to normalized `Normal`/`Tangent` attributes, where it costs a
+    /// quarter of `Float`'s footprint.
+    Int2_10_10_10,
+    /// Unsigned counterpart of `Int2_10_10_10`.
+    UInt2_10_10_10,
 }
 
 /// The possible pre-defined and named attributes in the vertex component, describing
@@ -284,6 +304,44 @@ impl VertexLayout {
 
         None
     }
+
+    /// Checks this layout for overlapping attributes, misaligned offsets,
+    /// and an overall `stride` past `max_stride`. `VertexLayoutBuilder`
+    /// already produces well-formed layouts, so this mainly guards layouts
+    /// assembled by hand (e.g. `CustomVertexLayoutBuilder`, or a layout
+    /// loaded from a serialized asset).
+    pub fn validate(&self, max_stride: u8) -> Result<()> {
+        if self.stride > max_stride {
+            bail!(ErrorKind::InvalidVertexLayout);
+        }
+
+        for i in 0..(self.len as usize) {
+            let align = align_of_vertex(self.elements[i].format);
+            if self.offset[i] % align != 0 {
+                bail!(ErrorKind::InvalidVertexLayout);
+            }
+
+            let end = self.offset[i] + self.elements[i].size * size_of_vertex(self.elements[i].format);
+            if end > self.stride {
+                bail!(ErrorKind::InvalidVertexLayout);
+            }
+
+            for j in 0..(self.len as usize) {
+                if i == j {
+                    continue;
+                }
+
+                let other_end = self.offset[j] +
+                                 self.elements[j].size * size_of_vertex(self.elements[j].format);
+                let overlaps = self.offset[i] < other_end && self.offset[j] < end;
+                if overlaps {
+                    bail!(ErrorKind::InvalidVertexLayout);
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Helper structure to build a vertex layout.
@@ -303,6 +361,9 @@ impl VertexLayoutBuilder {
                 normalized: bool)
                 -> &mut Self {
         assert!(size > 0 && size <= 4);
+        assert!(!is_packed_vertex_format(format) || size == 4,
+                "{:?} packs x/y/z/w into a single word, so `size` must be 4.",
+                format);
 
         let desc = VertexAttributeDesc {
             name: attribute,
@@ -326,24 +387,210 @@ impl VertexLayoutBuilder {
         self
     }
 
+    /// Finishes the layout, padding each attribute's offset up to its
+    /// format's natural alignment (so the GPU never reads a multi-byte
+    /// component from a misaligned address) and the final `stride` up to
+    /// the widest alignment in use.
     #[inline]
     pub fn finish(&mut self) -> VertexLayout {
         self.0.stride = 0;
+        let mut max_align = 1;
         for i in 0..self.0.len {
             let i = i as usize;
-            let len = self.0.elements[i].size * size_of_vertex(self.0.elements[i].format);
+            let align = align_of_vertex(self.0.elements[i].format);
+            max_align = max_align.max(align);
+
+            self.0.stride = align_up(self.0.stride, align);
             self.0.offset[i] = self.0.stride;
+
+            let len = self.0.elements[i].size * size_of_vertex(self.0.elements[i].format);
             self.0.stride += len;
         }
+        self.0.stride = align_up(self.0.stride, max_align);
         self.0
     }
 }
 
+/// Rounds `value` up to the next multiple of `align`, which must be a power
+/// of two.
+fn align_up(value: u8, align: u8) -> u8 {
+    (value + align - 1) / align * align
+}
+
+/// The natural alignment, in bytes, of a single vertex component stored as
+/// `format` -- the smallest address boundary the GPU can read it from.
+fn align_of_vertex(format: VertexFormat) -> u8 {
+    match format {
+        VertexFormat::Byte | VertexFormat::UByte => 1,
+        VertexFormat::Short | VertexFormat::UShort | VertexFormat::Half => 2,
+        VertexFormat::Float => 4,
+        VertexFormat::Int2_10_10_10 | VertexFormat::UInt2_10_10_10 => 4,
+    }
+}
+
 fn size_of_vertex(format: VertexFormat) -> u8 {
     match format {
         VertexFormat::Byte | VertexFormat::UByte => 1,
-        VertexFormat::Short | VertexFormat::UShort => 2,
+        VertexFormat::Short | VertexFormat::UShort | VertexFormat::Half => 2,
         VertexFormat::Float => 4,
+        // Packed into a single 4-byte word regardless of component count, so
+        // `size (4) * size_of_vertex (1)` yields the right 4-byte total.
+        VertexFormat::Int2_10_10_10 | VertexFormat::UInt2_10_10_10 => 1,
+    }
+}
+
+/// `true` if `format` packs all of its components into a single machine
+/// word rather than storing one machine word per component, i.e. `size`
+/// must be exactly 4 (one value for each of x, y, z, w).
+fn is_packed_vertex_format(format: VertexFormat) -> bool {
+    match format {
+        VertexFormat::Int2_10_10_10 | VertexFormat::UInt2_10_10_10 => true,
+        _ => false,
+    }
+}
+
+impl_handle!(MeshHandle);
+
+/// One contiguous range of a `Mesh`'s index buffer, assembled as `primitive`.
+/// A `Mesh` is cut into one or more of these so a single vertex/index buffer
+/// pair can carry several draw calls (e.g. one per material).
+#[derive(Debug, Copy, Clone)]
+pub struct SubMesh {
+    /// How this range's indices are assembled into primitives.
+    pub primitive: Primitive,
+    /// Index of the first index belonging to this range.
+    pub offset: u32,
+    /// Number of indices in this range.
+    pub count: u32,
+}
+
+/// An axis-aligned bounding box, stored as opposing corners.
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+/// Ties a `VertexBufferHandle` and `IndexBufferHandle` together into a
+/// single, cullable, multi-material draw unit: one or more `SubMesh` draw
+/// ranges over the same buffers, plus the bounding box of the whole mesh
+/// (and of each sub-mesh) derived from the `Position` attribute.
+#[derive(Debug, Clone)]
+pub struct MeshSetup {
+    pub vertex_buffer: VertexBufferHandle,
+    pub index_buffer: IndexBufferHandle,
+    pub sub_meshes: Vec<SubMesh>,
+    aabb: Aabb,
+    sub_mesh_aabbs: Vec<Aabb>,
+}
+
+impl MeshSetup {
+    /// Builds a `MeshSetup` from buffer handles and sub-mesh ranges,
+    /// deriving the overall and per-sub-mesh bounding boxes from `verts`
+    /// (interleaved per `layout`) and the indices each sub-mesh range
+    /// references out of `idxes` (laid out per `index_format`).
+    ///
+    /// Panics if `layout` has no `Position` attribute.
+    pub fn new(vertex_buffer: VertexBufferHandle,
+               index_buffer: IndexBufferHandle,
+               sub_meshes: Vec<SubMesh>,
+               layout: &VertexLayout,
+               verts: &[u8],
+               index_format: IndexFormat,
+               idxes: &[u8])
+               -> Self {
+        // `VertexLayout::offset` also matches unused slots, whose default name
+        // is `Position`, so only the `len` declared elements are searched.
+        let position_offset = (0..layout.len as usize)
+            .find(|&i| layout.elements[i].name == VertexAttribute::Position)
+            .map(|i| layout.offset[i])
+            .expect("Mesh requires a Position attribute to compute its bounding box.") as
+                               usize;
+        let stride = layout.stride() as usize;
+
+        let read_position = |vertex: u32| -> [f32; 3] {
+            let base = vertex as usize * stride + position_offset;
+            unsafe {
+                [
+                    *(verts.as_ptr().offset(base as isize) as *const f32),
+                    *(verts.as_ptr().offset((base + 4) as isize) as *const f32),
+                    *(verts.as_ptr().offset((base + 8) as isize) as *const f32),
+                ]
+            }
+        };
+
+        let read_index = |index: u32| -> u32 {
+            unsafe {
+                match index_format {
+                    IndexFormat::U16 => {
+                        *(idxes.as_ptr().offset((index as usize * 2) as isize) as *const u16) as
+                            u32
+                    }
+                    IndexFormat::U32 => {
+                        *(idxes.as_ptr().offset((index as usize * 4) as isize) as *const u32)
+                    }
+                }
+            }
+        };
+
+        let mut aabb = Aabb {
+            min: [::std::f32::MAX; 3],
+            max: [::std::f32::MIN; 3],
+        };
+        let mut sub_mesh_aabbs = Vec::with_capacity(sub_meshes.len());
+
+        for sub in &sub_meshes {
+            let mut sub_aabb = Aabb {
+                min: [::std::f32::MAX; 3],
+                max: [::std::f32::MIN; 3],
+            };
+
+            for i in sub.offset..(sub.offset + sub.count) {
+                let p = read_position(read_index(i));
+                for c in 0..3 {
+                    sub_aabb.min[c] = sub_aabb.min[c].min(p[c]);
+                    sub_aabb.max[c] = sub_aabb.max[c].max(p[c]);
+                    aabb.min[c] = aabb.min[c].min(p[c]);
+                    aabb.max[c] = aabb.max[c].max(p[c]);
+                }
+            }
+
+            sub_mesh_aabbs.push(sub_aabb);
+        }
+
+        MeshSetup {
+            vertex_buffer: vertex_buffer,
+            index_buffer: index_buffer,
+            sub_meshes: sub_meshes,
+            aabb: aabb,
+            sub_mesh_aabbs: sub_mesh_aabbs,
+        }
+    }
+
+    /// The bounding box of the whole mesh.
+    #[inline]
+    pub fn aabb(&self) -> Aabb {
+        self.aabb
+    }
+
+    /// The bounding box of a single sub-mesh, for frustum culling at a finer
+    /// grain than the whole mesh.
+    #[inline]
+    pub fn sub_mesh_aabb(&self, index: usize) -> Option<Aabb> {
+        self.sub_mesh_aabbs.get(index).cloned()
+    }
+
+    /// Total number of assembled primitives across every sub-mesh.
+    pub fn num_primitives(&self) -> u32 {
+        self.sub_meshes.iter().map(|sub| sub.primitive.assemble(sub.count)).sum()
+    }
+
+    /// Total number of assembled triangles across every sub-mesh.
+    pub fn num_triangles(&self) -> u32 {
+        self.sub_meshes
+            .iter()
+            .map(|sub| sub.primitive.assemble_triangles(sub.count))
+            .sum()
     }
 }
 
@@ -389,6 +636,48 @@ mod test {
         assert_eq!(element.normalized, true);
         assert_eq!(layout.element(VertexAttribute::Normal), None);
     }
+
+    #[test]
+    fn packed_and_half_formats() {
+        // `Half` x3 is 6 bytes; the following 4-byte-aligned packed format
+        // pads its offset from 6 up to 8, and the final stride pads from
+        // 12 up to the widest alignment in use (4).
+        let layout = VertexLayout::build()
+            .with(VertexAttribute::Position, VertexFormat::Half, 3, false)
+            .with(VertexAttribute::Normal, VertexFormat::Int2_10_10_10, 4, true)
+            .finish();
+
+        assert_eq!(layout.offset(VertexAttribute::Position), Some(0));
+        assert_eq!(layout.offset(VertexAttribute::Normal), Some(8));
+        assert_eq!(layout.stride(), 12);
+        assert!(layout.validate(32).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_overlap() {
+        let mut layout = VertexLayout::build()
+            .with(VertexAttribute::Position, VertexFormat::Float, 3, false)
+            .finish();
+
+        // Hand-craft an overlapping second element, bypassing the builder
+        // (which never produces overlaps on its own).
+        layout.elements[1] = VertexAttributeDesc {
+            name: VertexAttribute::Normal,
+            format: VertexFormat::Float,
+            size: 3,
+            normalized: false,
+        };
+        layout.offset[1] = 4;
+        layout.len = 2;
+
+        assert!(layout.validate(255).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn packed_format_requires_size_four() {
+        VertexLayout::build().with(VertexAttribute::Normal, VertexFormat::Int2_10_10_10, 3, true);
+    }
 }
 
 #[macro_use]
@@ -413,6 +702,9 @@ pub mod macros {
                     offset_of_field: u8)
                     -> &mut Self {
             assert!(size > 0 && size <= 4);
+            assert!(!is_packed_vertex_format(format) || size == 4,
+                    "{:?} packs x/y/z/w into a single word, so `size` must be 4.",
+                    format);
 
             let desc = VertexAttributeDesc {
                 name: attribute,
@@ -477,7 +769,10 @@ pub mod macros {
                         $normalized,
                         offset_of!($name, $field) as u8); ) *
 
-                    builder.finish(::std::mem::size_of::<$name>() as u8)
+                    let layout = builder.finish(::std::mem::size_of::<$name>() as u8);
+                    layout.validate(::std::u8::MAX)
+                        .expect("impl_vertex! produced an invalid VertexLayout.");
+                    layout
                 }
 
                 pub fn as_bytes(values: &[Self]) -> &[u8] {
@@ -502,9 +797,14 @@ pub mod macros {
         (VertexFormat::UShort, 2) => ([u16; 2]);
         (VertexFormat::UShort, 3) => ([u16; 3]);
         (VertexFormat::UShort, 4) => ([u16; 4]);
+        (VertexFormat::Half, 2) => ([u16; 2]);
+        (VertexFormat::Half, 3) => ([u16; 3]);
+        (VertexFormat::Half, 4) => ([u16; 4]);
         (VertexFormat::Float, 2) => ([f32; 2]);
         (VertexFormat::Float, 3) => ([f32; 3]);
         (VertexFormat::Float, 4) => ([f32; 4]);
+        (VertexFormat::Int2_10_10_10, 4) => (u32);
+        (VertexFormat::UInt2_10_10_10, 4) => (u32);
     }
 
     #[cfg(test)]
@@ -0,0 +1,441 @@
+//! Parses glTF 2.0 (`.gltf`/`.glb`) documents into `MeshData`.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use gltf;
+
+use graphics::assets::mesh::*;
+use graphics::assets::mesh_loader::{MeshData, MeshParser};
+
+/// Parses the first mesh of a glTF document, merging every primitive into a
+/// single interleaved vertex buffer and a single index buffer, with one
+/// `sub_mesh_offsets` entry per primitive so each keeps its own draw range.
+pub struct GltfMeshParser;
+
+#[derive(Debug)]
+pub enum GltfMeshParserError {
+    Gltf(gltf::Error),
+    MissingMesh,
+    MissingPositions,
+}
+
+impl fmt::Display for GltfMeshParserError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GltfMeshParserError::Gltf(ref err) => write!(fmt, "{}", err),
+            GltfMeshParserError::MissingMesh => write!(fmt, "document has no meshes"),
+            GltfMeshParserError::MissingPositions => {
+                write!(fmt, "primitive is missing a POSITION accessor")
+            }
+        }
+    }
+}
+
+impl StdError for GltfMeshParserError {
+    fn description(&self) -> &str {
+        "failed to parse glTF mesh"
+    }
+}
+
+impl MeshParser for GltfMeshParser {
+    type Error = GltfMeshParserError;
+
+    fn parse(bytes: &[u8]) -> Result<MeshData, Self::Error> {
+        let (document, buffers, _images) =
+            gltf::import_slice(bytes).map_err(GltfMeshParserError::Gltf)?;
+        let mesh = document.meshes().next().ok_or(GltfMeshParserError::MissingMesh)?;
+
+        let include_skin_attributes = should_include_skin_attributes(
+            mesh_has_consuming_skin(&document, mesh.index()),
+            mesh.primitives().any(|p| primitive_has_skin_attributes(&p, &buffers)));
+
+        if !include_skin_attributes &&
+           mesh.primitives().any(|p| primitive_has_skin_attributes(&p, &buffers)) {
+            warn!("glTF mesh {:?} carries JOINTS_0/WEIGHTS_0 but is never placed on a \
+                   skinned node; dropping skinning attributes from its VertexLayout.",
+                  mesh.name().unwrap_or("<unnamed>"));
+        }
+
+        let layout = build_layout(include_skin_attributes);
+
+        let mut verts = Vec::new();
+        let mut idxes = Vec::new();
+        let mut sub_mesh_offsets = Vec::new();
+        let mut base_vertex = 0u32;
+
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|b| buffers.get(b.index()).map(|d| &d.0[..]));
+
+            let positions: Vec<[f32; 3]> = reader.read_positions()
+                .ok_or(GltfMeshParserError::MissingPositions)?
+                .collect();
+            let normals: Vec<[f32; 3]> = reader.read_normals()
+                .map(|it| it.collect())
+                .unwrap_or_else(|| vec![[0.0, 0.0, 1.0]; positions.len()]);
+            let texcoords: Vec<[f32; 2]> = reader.read_tex_coords(0)
+                .map(|it| it.into_f32().collect())
+                .unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]);
+            let joints: Vec<[u16; 4]> = if include_skin_attributes {
+                reader.read_joints(0)
+                    .map(|it| it.into_u16().collect())
+                    .unwrap_or_else(|| vec![[0; 4]; positions.len()])
+            } else {
+                Vec::new()
+            };
+            let weights: Vec<[f32; 4]> = if include_skin_attributes {
+                reader.read_weights(0)
+                    .map(|it| it.into_f32().collect())
+                    .unwrap_or_else(|| vec![[0.0; 4]; positions.len()])
+            } else {
+                Vec::new()
+            };
+
+            for i in 0..positions.len() {
+                verts.extend_from_slice(IndexFormat::as_bytes(&positions[i]));
+                verts.extend_from_slice(IndexFormat::as_bytes(&normals[i]));
+                verts.extend_from_slice(IndexFormat::as_bytes(&texcoords[i]));
+
+                if include_skin_attributes {
+                    let j = [joints[i][0] as u8, joints[i][1] as u8, joints[i][2] as u8,
+                             joints[i][3] as u8];
+                    verts.extend_from_slice(&j);
+                    verts.extend_from_slice(IndexFormat::as_bytes(&weights[i]));
+                }
+            }
+
+            if let Some(indices) = reader.read_indices() {
+                for index in indices.into_u32() {
+                    let vertex = index + base_vertex;
+                    idxes.extend_from_slice(IndexFormat::as_bytes(&[vertex]));
+                }
+            }
+
+            base_vertex += positions.len() as u32;
+            sub_mesh_offsets.push(idxes.len() / IndexFormat::U32.len());
+        }
+
+        Ok(MeshData {
+            layout: layout,
+            index_format: IndexFormat::U32,
+            primitive: MeshPrimitive::Triangles,
+            num_verts: base_vertex as usize,
+            num_idxes: idxes.len() / IndexFormat::U32.len(),
+            sub_mesh_offsets: sub_mesh_offsets,
+            verts: verts,
+            idxes: idxes,
+        })
+    }
+}
+
+/// `true` if any node placing `mesh_index` in `document` is bound to a skin.
+/// A mesh with joint/weight attributes but no such node is only warned
+/// about, not rejected -- those attributes simply go unused.
+fn mesh_has_consuming_skin(document: &gltf::Document, mesh_index: usize) -> bool {
+    document.nodes().any(|node| {
+        node.mesh().map(|m| m.index()) == Some(mesh_index) && node.skin().is_some()
+    })
+}
+
+fn primitive_has_skin_attributes(primitive: &gltf::Primitive, buffers: &[gltf::buffer::Data])
+                                  -> bool {
+    primitive.reader(|b| buffers.get(b.index()).map(|d| &d.0[..])).read_joints(0).is_some()
+}
+
+/// Whether the generated `VertexLayout` should keep `Indices`/`Weight`
+/// attributes: only when the mesh both carries joint/weight data *and* is
+/// actually placed on a node bound to a skin.
+fn should_include_skin_attributes(has_consuming_skin: bool, wants_skin_attributes: bool) -> bool {
+    has_consuming_skin && wants_skin_attributes
+}
+
+fn build_layout(include_skin_attributes: bool) -> VertexLayout {
+    let mut builder = VertexLayout::build();
+    builder.with(VertexAttribute::Position, VertexFormat::Float, 3, false);
+    builder.with(VertexAttribute::Normal, VertexFormat::Float, 3, false);
+    builder.with(VertexAttribute::Texcoord0, VertexFormat::Float, 2, false);
+
+    if include_skin_attributes {
+        builder.with(VertexAttribute::Indices, VertexFormat::UByte, 4, false);
+        builder.with(VertexAttribute::Weight, VertexFormat::Float, 4, false);
+    }
+
+    builder.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn skin_attributes_require_a_consuming_skin() {
+        assert_eq!(should_include_skin_attributes(false, true), false);
+        assert_eq!(should_include_skin_attributes(true, false), false);
+        assert_eq!(should_include_skin_attributes(true, true), true);
+    }
+
+    #[test]
+    fn layout_without_skin_omits_joint_attributes() {
+        let layout = build_layout(false);
+        assert!(layout.offset(VertexAttribute::Indices).is_none());
+        assert!(layout.offset(VertexAttribute::Weight).is_none());
+    }
+
+    #[test]
+    fn layout_with_skin_includes_joint_attributes() {
+        let layout = build_layout(true);
+        assert!(layout.offset(VertexAttribute::Indices).is_some());
+        assert!(layout.offset(VertexAttribute::Weight).is_some());
+    }
+
+    // -- Hand-built, embedded `.gltf` JSON documents (buffers inlined as
+    // `data:` URIs) exercising `GltfMeshParser::parse` end-to-end, since the
+    // unit tests above only cover its two private helpers in isolation.
+
+    fn base64_encode(data: &[u8]) -> String {
+        const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in data.chunks(3) {
+            let b0 = chunk[0] as u32;
+            let b1 = *chunk.get(1).unwrap_or(&0) as u32;
+            let b2 = *chunk.get(2).unwrap_or(&0) as u32;
+            let n = (b0 << 16) | (b1 << 8) | b2;
+            out.push(CHARS[((n >> 18) & 0x3F) as usize] as char);
+            out.push(CHARS[((n >> 12) & 0x3F) as usize] as char);
+            out.push(if chunk.len() > 1 {
+                          CHARS[((n >> 6) & 0x3F) as usize] as char
+                      } else {
+                          '='
+                      });
+            out.push(if chunk.len() > 2 {
+                          CHARS[(n & 0x3F) as usize] as char
+                      } else {
+                          '='
+                      });
+        }
+        out
+    }
+
+    fn positions_bytes(positions: &[[f32; 3]]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for p in positions {
+            for &c in p {
+                bytes.extend_from_slice(&c.to_bits().to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    fn indices_bytes(indices: &[u16]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for &i in indices {
+            bytes.extend_from_slice(&i.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn data_uri(bytes: &[u8]) -> String {
+        format!("data:application/octet-stream;base64,{}", base64_encode(bytes))
+    }
+
+    /// A single-triangle, single-primitive `.gltf`, optionally carrying
+    /// `JOINTS_0`/`WEIGHTS_0` attribute data and optionally placed on a
+    /// skin-bound node, to exercise the skin/no-skin mismatch path.
+    fn single_primitive_gltf(include_skin_data: bool, node_has_skin: bool) -> Vec<u8> {
+        let positions = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        let indices = [0u16, 1, 2];
+
+        let mut buf = positions_bytes(&positions);
+        let indices_offset = buf.len();
+        buf.extend_from_slice(&indices_bytes(&indices));
+
+        let mut accessors = format!(
+            r#"{{"bufferView":0,"byteOffset":0,"componentType":5126,"count":3,"type":"VEC3"}},
+               {{"bufferView":0,"byteOffset":{},"componentType":5123,"count":3,"type":"SCALAR"}}"#,
+            indices_offset);
+        let mut attributes = String::from(r#""POSITION":0"#);
+
+        if include_skin_data {
+            let joints_offset = buf.len();
+            for _ in 0..positions.len() {
+                buf.extend_from_slice(&[0u8, 0, 0, 0]);
+            }
+            let weights_offset = buf.len();
+            for _ in 0..positions.len() {
+                for _ in 0..4 {
+                    buf.extend_from_slice(&1.0f32.to_bits().to_le_bytes());
+                }
+            }
+
+            accessors.push_str(&format!(
+                r#",{{"bufferView":0,"byteOffset":{},"componentType":5121,"count":3,"type":"VEC4"}},
+                   {{"bufferView":0,"byteOffset":{},"componentType":5126,"count":3,"type":"VEC4"}}"#,
+                joints_offset,
+                weights_offset));
+            attributes.push_str(r#","JOINTS_0":2,"WEIGHTS_0":3"#);
+        }
+
+        let (skins, nodes) = if node_has_skin {
+            (r#""skins":[{"joints":[1]}],"#, r#"{"mesh":0,"skin":0},{}"#)
+        } else {
+            ("", r#"{"mesh":0}"#)
+        };
+
+        format!(
+            r#"{{
+                "asset":{{"version":"2.0"}},
+                {skins}
+                "nodes":[{nodes}],
+                "meshes":[{{"primitives":[{{"attributes":{{{attributes}}},"indices":1}}]}}],
+                "accessors":[{accessors}],
+                "bufferViews":[{{"buffer":0,"byteOffset":0,"byteLength":{len}}}],
+                "buffers":[{{"byteLength":{len},"uri":"{uri}"}}]
+            }}"#,
+            skins = skins,
+            nodes = nodes,
+            attributes = attributes,
+            accessors = accessors,
+            len = buf.len(),
+            uri = data_uri(&buf))
+                .into_bytes()
+    }
+
+    #[test]
+    fn parse_strips_skin_attributes_without_a_consuming_skin() {
+        let bytes = single_primitive_gltf(true, false);
+        let data = GltfMeshParser::parse(&bytes).expect("valid glTF document");
+
+        assert!(data.layout.offset(VertexAttribute::Indices).is_none());
+        assert!(data.layout.offset(VertexAttribute::Weight).is_none());
+        assert_eq!(data.num_verts, 3);
+        assert_eq!(data.num_idxes, 3);
+    }
+
+    #[test]
+    fn parse_keeps_skin_attributes_with_a_consuming_skin() {
+        let bytes = single_primitive_gltf(true, true);
+        let data = GltfMeshParser::parse(&bytes).expect("valid glTF document");
+
+        assert!(data.layout.offset(VertexAttribute::Indices).is_some());
+        assert!(data.layout.offset(VertexAttribute::Weight).is_some());
+        assert_eq!(data.num_verts, 3);
+        assert_eq!(data.num_idxes, 3);
+    }
+
+    /// Two triangles, each a separate primitive reading from its *own*
+    /// buffer (as opposed to the interleaved-attributes case below), merged
+    /// by `parse` into one interleaved vertex buffer and one sub-mesh per
+    /// primitive.
+    #[test]
+    fn parse_merges_two_primitives_from_separate_buffers() {
+        let positions_a = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        let positions_b = [[0.0f32, 0.0, 1.0], [1.0, 0.0, 1.0], [0.0, 1.0, 1.0]];
+        let indices = [0u16, 1, 2];
+
+        let mut buf_a = positions_bytes(&positions_a);
+        let indices_offset_a = buf_a.len();
+        buf_a.extend_from_slice(&indices_bytes(&indices));
+
+        let mut buf_b = positions_bytes(&positions_b);
+        let indices_offset_b = buf_b.len();
+        buf_b.extend_from_slice(&indices_bytes(&indices));
+
+        let json = format!(
+            r#"{{
+                "asset":{{"version":"2.0"}},
+                "nodes":[{{"mesh":0}}],
+                "meshes":[{{"primitives":[
+                    {{"attributes":{{"POSITION":0}},"indices":1}},
+                    {{"attributes":{{"POSITION":2}},"indices":3}}
+                ]}}],
+                "accessors":[
+                    {{"bufferView":0,"byteOffset":0,"componentType":5126,"count":3,"type":"VEC3"}},
+                    {{"bufferView":0,"byteOffset":{idx_a},"componentType":5123,"count":3,"type":"SCALAR"}},
+                    {{"bufferView":1,"byteOffset":0,"componentType":5126,"count":3,"type":"VEC3"}},
+                    {{"bufferView":1,"byteOffset":{idx_b},"componentType":5123,"count":3,"type":"SCALAR"}}
+                ],
+                "bufferViews":[
+                    {{"buffer":0,"byteOffset":0,"byteLength":{len_a}}},
+                    {{"buffer":1,"byteOffset":0,"byteLength":{len_b}}}
+                ],
+                "buffers":[
+                    {{"byteLength":{len_a},"uri":"{uri_a}"}},
+                    {{"byteLength":{len_b},"uri":"{uri_b}"}}
+                ]
+            }}"#,
+            idx_a = indices_offset_a,
+            idx_b = indices_offset_b,
+            len_a = buf_a.len(),
+            len_b = buf_b.len(),
+            uri_a = data_uri(&buf_a),
+            uri_b = data_uri(&buf_b));
+
+        let data = GltfMeshParser::parse(json.as_bytes()).expect("valid glTF document");
+
+        assert_eq!(data.num_verts, 6);
+        assert_eq!(data.num_idxes, 6);
+        assert_eq!(data.sub_mesh_offsets, vec![3, 6]);
+    }
+
+    /// A single primitive whose POSITION/NORMAL/TEXCOORD_0 accessors all
+    /// read from one `bufferView` with a non-zero `byteStride`, i.e. the
+    /// source data is itself interleaved per-vertex rather than laid out in
+    /// separate attribute blocks.
+    #[test]
+    fn parse_reads_position_out_of_an_interleaved_source_buffer() {
+        let vertices: [([f32; 3], [f32; 3], [f32; 2]); 3] =
+            [([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0]),
+             ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0]),
+             ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0])];
+
+        let stride = 12 + 12 + 8;
+        let mut buf = Vec::new();
+        for &(position, normal, texcoord) in &vertices {
+            for &c in &position {
+                buf.extend_from_slice(&c.to_bits().to_le_bytes());
+            }
+            for &c in &normal {
+                buf.extend_from_slice(&c.to_bits().to_le_bytes());
+            }
+            for &c in &texcoord {
+                buf.extend_from_slice(&c.to_bits().to_le_bytes());
+            }
+        }
+
+        let indices_offset = buf.len();
+        buf.extend_from_slice(&indices_bytes(&[0, 1, 2]));
+
+        let json = format!(
+            r#"{{
+                "asset":{{"version":"2.0"}},
+                "nodes":[{{"mesh":0}}],
+                "meshes":[{{"primitives":[
+                    {{"attributes":{{"POSITION":0,"NORMAL":1,"TEXCOORD_0":2}},"indices":3}}
+                ]}}],
+                "accessors":[
+                    {{"bufferView":0,"byteOffset":0,"componentType":5126,"count":3,"type":"VEC3"}},
+                    {{"bufferView":0,"byteOffset":12,"componentType":5126,"count":3,"type":"VEC3"}},
+                    {{"bufferView":0,"byteOffset":24,"componentType":5126,"count":3,"type":"VEC2"}},
+                    {{"bufferView":1,"byteOffset":0,"componentType":5123,"count":3,"type":"SCALAR"}}
+                ],
+                "bufferViews":[
+                    {{"buffer":0,"byteOffset":0,"byteLength":{stride_len},"byteStride":{stride}}},
+                    {{"buffer":0,"byteOffset":{idx},"byteLength":6}}
+                ],
+                "buffers":[
+                    {{"byteLength":{len},"uri":"{uri}"}}
+                ]
+            }}"#,
+            stride_len = stride * vertices.len(),
+            stride = stride,
+            idx = indices_offset,
+            len = buf.len(),
+            uri = data_uri(&buf));
+
+        let data = GltfMeshParser::parse(json.as_bytes()).expect("valid glTF document");
+
+        assert_eq!(data.num_verts, 3);
+        assert_eq!(data.num_idxes, 3);
+        assert_eq!(data.sub_mesh_offsets, vec![3]);
+    }
+}
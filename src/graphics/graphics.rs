@@ -1,9 +1,9 @@
 //! The centralized management of video sub-system.
 
-use std::sync::{Arc, RwLock};
-use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::collections::{HashMap, HashSet};
 
-use utils::{Rect, HashValue};
+use utils::{Rect, HashValue, Handle};
 use resource;
 use resource::{ResourceSystemShared, Registery};
 
@@ -15,6 +15,14 @@ use super::command::Command;
 use super::window::Window;
 use super::assets::texture_loader::{TextureLoader, TextureParser, TextureState};
 
+/// Default number of further frames a deleted resource's destruction is
+/// delayed by, see `GraphicsSystemShared::set_max_frames_in_flight`.
+const DEFAULT_MAX_FRAMES_IN_FLIGHT: usize = 2;
+
+/// Number of frames a pooled texture may sit idle before
+/// `GraphicsSystemShared::frame_maintenance` destroys it for real.
+const TEXTURE_POOL_MAX_IDLE_FRAMES: usize = 120;
+
 /// The centralized management of video sub-system.
 pub struct GraphicsSystem {
     window: Arc<Window>,
@@ -24,6 +32,16 @@ pub struct GraphicsSystem {
 
     last_dimensions: (u32, u32),
     last_hidpi: f32,
+
+    // Monotonically increasing count of frames advanced, fed to
+    // `GraphicsSystemShared::frame_maintenance` so pooled/retired resources
+    // age out deterministically.
+    frame_index: usize,
+
+    // OpenGL timer queries are asynchronous, so results written this frame are
+    // not necessarily available yet. Buffer a handful of in-flight frames'
+    // worth of query sets and pop the oldest that's ready every `advance`.
+    gpu_timings: ::std::collections::VecDeque<HashMap<SurfaceHandle, u64>>,
 }
 
 impl GraphicsSystem {
@@ -44,11 +62,13 @@ impl GraphicsSystem {
         Ok(GraphicsSystem {
                last_dimensions: dimensions,
                last_hidpi: window.hidpi_factor(),
+               frame_index: 0,
 
                window: window,
                device: device,
                frames: frames,
                shared: Arc::new(shared),
+               gpu_timings: ::std::collections::VecDeque::new(),
            })
     }
 
@@ -57,6 +77,22 @@ impl GraphicsSystem {
         self.shared.clone()
     }
 
+    /// Creates a texture and wraps it in an `RAIIGuard`, so it is deleted
+    /// automatically if the guard is dropped without an explicit `forget`.
+    pub fn create_texture_owned(&self,
+                                setup: TextureSetup,
+                                data: Option<&[u8]>)
+                                -> Result<guard::RAIIGuard<TextureHandle>> {
+        let handle = self.shared.create_texture(setup, data)?;
+        Ok(self.acquire(handle))
+    }
+
+    /// Wraps an already-created texture `handle` in an `RAIIGuard`, transferring
+    /// ownership of its lifetime to the guard.
+    pub fn acquire(&self, handle: TextureHandle) -> guard::RAIIGuard<TextureHandle> {
+        guard::RAIIGuard::new(self.shared.clone(), handle)
+    }
+
     /// Swap internal commands frame.
     #[inline]
     pub fn swap_frames(&self) {
@@ -115,6 +151,15 @@ impl GraphicsSystem {
 
             info.duration = time::Instant::now() - ts;
 
+            // GPU timer queries resolve asynchronously: the results read back this
+            // frame belong to queries submitted a frame (or two) ago, so pop the
+            // oldest buffered entry before pushing this frame's freshly-submitted
+            // query set onto the back of the queue.
+            if let Some(timings) = self.gpu_timings.pop_front() {
+                info.gpu_times = timings;
+            }
+            self.gpu_timings.push_back(self.device.resolve_query_results());
+
             {
                 let s = &self.shared;
                 info.alive_surfaces = Self::clear(&mut s.surfaces.write().unwrap());
@@ -127,6 +172,10 @@ impl GraphicsSystem {
                 info.alive_render_buffers = Self::clear(&mut s.render_buffers.write().unwrap());
             }
 
+            self.frame_index = self.frame_index.wrapping_add(1);
+            self.shared.frame_maintenance(self.frame_index, TEXTURE_POOL_MAX_IDLE_FRAMES);
+            self.shared.retire_frame();
+
             Ok(info)
         }
     }
@@ -139,8 +188,150 @@ impl GraphicsSystem {
     }
 }
 
+/// Diagnostic information gathered during a single `GraphicsSystem::advance`.
+#[derive(Debug, Clone, Default)]
+pub struct GraphicsFrameInfo {
+    pub duration: ::std::time::Duration,
+    pub drawcall: usize,
+    pub triangles: usize,
+
+    pub alive_surfaces: usize,
+    pub alive_shaders: usize,
+    pub alive_frame_buffers: usize,
+    pub alive_vertex_buffers: usize,
+    pub alive_index_buffers: usize,
+    pub alive_textures: usize,
+    pub alive_render_buffers: usize,
+
+    /// GPU nanoseconds spent per surface bucket, as measured by
+    /// `Command::BeginTimeElapsed`/`EndTimeElapsed` pairs.
+    ///
+    /// Because OpenGL timer queries are asynchronous, `advance` holds each
+    /// call's read-back results for one call: this reflects the results read
+    /// back during the previous `advance` call, which belong to queries
+    /// written one or more frames earlier rather than this one.
+    pub gpu_times: HashMap<SurfaceHandle, u64>,
+}
+
 type ShaderState = HashMap<HashValue<str>, usize>;
 
+/// Describes a compute pipeline, mirroring `ShaderSetup` but for a single
+/// compute stage.
+#[derive(Debug, Clone)]
+pub struct ComputeShaderSetup {
+    /// The GLSL compute shader source.
+    pub cs: String,
+    /// The names of the uniform variables the compute shader expects.
+    pub uniform_variables: Vec<String>,
+}
+
+impl_handle!(ComputeShaderHandle);
+
+/// Handle to a storage buffer, a GPU-visible buffer that a compute shader can
+/// read from and write to and that a later draw call can consume as input.
+#[derive(Debug, Copy, Clone)]
+pub struct StorageBufferSetup {
+    /// Usage hints, same semantics as `VertexBufferSetup`/`IndexBufferSetup`.
+    pub hint: BufferHint,
+    /// The length of the buffer, in bytes.
+    pub len: usize,
+}
+
+impl_handle!(StorageBufferHandle);
+
+impl_handle!(BundleHandle);
+
+impl_handle!(QuerySetHandle);
+
+impl_handle!(ReadbackHandle);
+
+/// Mirrors `TextureState::NotReady`/`Ready`, but for a pending GPU->CPU readback
+/// of a texture region or a storage/vertex buffer range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadbackState {
+    /// The readback has been staged but the bytes are not mapped yet.
+    NotReady,
+    /// The staging buffer has been mapped and its contents copied out.
+    Ready(Vec<u8>),
+}
+
+/// A pre-packed, immutable set of draw calls recorded by `create_command_bundle`.
+///
+/// The uniforms are extended into a buffer owned by the bundle itself (rather
+/// than the per-frame `DoubleFrame` buffer), so a bundle survives across frames
+/// and can be replayed with a single `FrameTask::ExecuteBundle` instead of
+/// re-encoding every draw call each frame.
+struct Bundle {
+    buf: ::utils::DataBuffer,
+    tasks: Vec<FrameTask>,
+    // Resource handles referenced by this bundle, revalidated on every
+    // `submit_bundle` so a bundle referencing a freed resource is refused
+    // rather than replayed against garbage.
+    vertex_buffers: Vec<VertexBufferHandle>,
+    index_buffers: Vec<IndexBufferHandle>,
+    shaders: Vec<ShaderHandle>,
+}
+
+/// The GL binding target a `create_external_texture` id should be sampled
+/// through. `ExternalOES` covers the `GL_TEXTURE_EXTERNAL_OES` frames handed
+/// out by Android's `SurfaceTexture`/camera and video decoder APIs, which
+/// need a dedicated `samplerExternalOES` uniform and `#extension` in GLES
+/// shaders rather than the regular `sampler2D`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternalTextureTarget {
+    Texture2D,
+    ExternalOES,
+}
+
+/// A retired texture kept alive in `GraphicsSystemShared::texture_pool`,
+/// waiting to either be recycled by a matching `create_texture` or swept by
+/// `frame_maintenance` once it has sat idle for too long.
+struct PooledTexture {
+    handle: TextureHandle,
+    retired_frame: usize,
+}
+
+/// A coarse-grained bucket used by the resource tracker and `ResourceReport`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceCategory {
+    Texture,
+    VertexBuffer,
+    IndexBuffer,
+    StorageBuffer,
+}
+
+/// One live allocation recorded by the resource tracker.
+struct ResourceRecord {
+    category: ResourceCategory,
+    /// The `Debug` representation of the setup the resource was created
+    /// with, e.g. for grepping a report for a particular texture format.
+    descriptor: String,
+    /// Estimated VRAM footprint in bytes.
+    size: usize,
+    /// An optional caller-supplied label, set via the `_labelled` creation
+    /// methods, to make a leak report human-readable.
+    label: Option<String>,
+}
+
+/// Live-allocation bookkeeping for `GraphicsSystemShared::resource_report`,
+/// disabled by default. While disabled nothing is recorded, though every
+/// tracked create/delete still formats its key and locks the tracker to check.
+#[derive(Default)]
+struct ResourceTracker {
+    enabled: bool,
+    live: HashMap<String, ResourceRecord>,
+    live_bytes: HashMap<ResourceCategory, usize>,
+    peak_bytes: HashMap<ResourceCategory, usize>,
+}
+
+/// A snapshot returned by `GraphicsSystemShared::resource_report`.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceReport {
+    pub live_counts: HashMap<ResourceCategory, usize>,
+    pub live_bytes: HashMap<ResourceCategory, usize>,
+    pub peak_bytes: HashMap<ResourceCategory, usize>,
+}
+
 /// The multi-thread friendly parts of `GraphicsSystem`.
 pub struct GraphicsSystemShared {
     resource: Arc<ResourceSystemShared>,
@@ -149,11 +340,43 @@ pub struct GraphicsSystemShared {
 
     surfaces: RwLock<Registery<()>>,
     shaders: RwLock<Registery<ShaderState>>,
+    compute_shaders: RwLock<Registery<ShaderState>>,
     framebuffers: RwLock<Registery<()>>,
     render_buffers: RwLock<Registery<()>>,
     vertex_buffers: RwLock<Registery<()>>,
     index_buffers: RwLock<Registery<()>>,
+    storage_buffers: RwLock<Registery<()>>,
     textures: RwLock<Registery<Arc<RwLock<TextureState>>>>,
+    bundles: RwLock<Registery<Bundle>>,
+    query_sets: RwLock<Registery<usize>>,
+    readbacks: RwLock<Registery<Arc<RwLock<ReadbackState>>>>,
+    shader_includes: RwLock<HashMap<String, String>>,
+
+    // Descriptor (the `Debug` representation of the creating `TextureSetup`)
+    // recorded per live texture, so `delete_texture` knows which pool bucket
+    // to return a freed texture to.
+    texture_descriptors: RwLock<HashMap<TextureHandle, String>>,
+    // Handles wrapping a caller-owned GL texture id via `create_external_texture`,
+    // so `delete_texture` knows to free only the bookkeeping and never emit a
+    // `PostFrameTask::DeleteTexture` that would destroy a texture we don't own.
+    external_textures: RwLock<HashSet<TextureHandle>>,
+    // Freed textures kept around for reuse by a future `create_texture` with
+    // a matching descriptor, instead of immediately destroying the GPU object.
+    texture_pool: Mutex<HashMap<String, Vec<PooledTexture>>>,
+    // The frame index last reported to `frame_maintenance`, stamped onto
+    // textures as they're retired into `texture_pool`.
+    texture_pool_frame: RwLock<usize>,
+
+    // How many further frames must be retired before a `retire`d task is
+    // actually dispatched, so a resource freed this frame isn't destroyed
+    // while GPU commands from frames still in flight might reference it.
+    max_frames_in_flight: RwLock<usize>,
+    // A ring of not-yet-due delete tasks, one bucket per frame still in
+    // flight; `retire_frame` pushes a fresh bucket and flushes the oldest
+    // once the ring is deeper than `max_frames_in_flight`.
+    retirement: Mutex<::std::collections::VecDeque<Vec<PostFrameTask>>>,
+
+    resource_tracker: Mutex<ResourceTracker>,
 }
 
 impl GraphicsSystemShared {
@@ -170,11 +393,152 @@ impl GraphicsSystemShared {
 
             surfaces: RwLock::new(Registery::new()),
             shaders: RwLock::new(Registery::new()),
+            compute_shaders: RwLock::new(Registery::new()),
             framebuffers: RwLock::new(Registery::new()),
             render_buffers: RwLock::new(Registery::new()),
             vertex_buffers: RwLock::new(Registery::new()),
             index_buffers: RwLock::new(Registery::new()),
+            storage_buffers: RwLock::new(Registery::new()),
             textures: RwLock::new(Registery::new()),
+            bundles: RwLock::new(Registery::new()),
+            query_sets: RwLock::new(Registery::new()),
+            readbacks: RwLock::new(Registery::new()),
+            shader_includes: RwLock::new(HashMap::new()),
+
+            texture_descriptors: RwLock::new(HashMap::new()),
+            external_textures: RwLock::new(HashSet::new()),
+            texture_pool: Mutex::new(HashMap::new()),
+            texture_pool_frame: RwLock::new(0),
+
+            max_frames_in_flight: RwLock::new(DEFAULT_MAX_FRAMES_IN_FLIGHT),
+            retirement: Mutex::new(::std::collections::VecDeque::new()),
+
+            resource_tracker: Mutex::new(ResourceTracker::default()),
+        }
+    }
+
+    /// Turns the resource tracking/leak-detection subsystem on or off.
+    /// Disabled by default; while disabled, each tracked create/delete call
+    /// still formats its tracking key and locks the tracker to check this
+    /// flag, but records nothing.
+    pub fn enable_resource_tracking(&self, enabled: bool) {
+        self.resource_tracker.lock().unwrap().enabled = enabled;
+    }
+
+    fn track_alloc(&self,
+                   category: ResourceCategory,
+                   key: String,
+                   descriptor: String,
+                   size: usize,
+                   label: Option<String>) {
+        let mut tracker = self.resource_tracker.lock().unwrap();
+        if !tracker.enabled {
+            return;
+        }
+
+        let live = {
+            let bytes = tracker.live_bytes.entry(category).or_insert(0);
+            *bytes += size;
+            *bytes
+        };
+
+        let peak = tracker.peak_bytes.entry(category).or_insert(0);
+        if live > *peak {
+            *peak = live;
+        }
+
+        tracker.live
+            .insert(key,
+                     ResourceRecord {
+                         category: category,
+                         descriptor: descriptor,
+                         size: size,
+                         label: label,
+                     });
+    }
+
+    fn track_dealloc(&self, category: ResourceCategory, key: &str) {
+        let mut tracker = self.resource_tracker.lock().unwrap();
+        if !tracker.enabled {
+            return;
+        }
+
+        if let Some(record) = tracker.live.remove(key) {
+            if let Some(bytes) = tracker.live_bytes.get_mut(&category) {
+                *bytes = bytes.saturating_sub(record.size);
+            }
+        }
+    }
+
+    /// Returns a snapshot of current live counts/bytes and the peak bytes ever
+    /// recorded, per `ResourceCategory`. Empty unless `enable_resource_tracking`
+    /// has been turned on.
+    pub fn resource_report(&self) -> ResourceReport {
+        let tracker = self.resource_tracker.lock().unwrap();
+
+        let mut live_counts = HashMap::new();
+        for record in tracker.live.values() {
+            *live_counts.entry(record.category).or_insert(0) += 1;
+        }
+
+        ResourceReport {
+            live_counts: live_counts,
+            live_bytes: tracker.live_bytes.clone(),
+            peak_bytes: tracker.peak_bytes.clone(),
+        }
+    }
+
+    /// Logs every handle that was allocated but never released. Intended to
+    /// be called once during shutdown, after every system has had a chance to
+    /// release its resources; anything still reported here leaked.
+    pub fn assert_no_leaks(&self) {
+        let tracker = self.resource_tracker.lock().unwrap();
+        for (key, record) in tracker.live.iter() {
+            warn!("leaked {:?} {} ({} bytes){}",
+                  record.category,
+                  key,
+                  record.size,
+                  record.label
+                      .as_ref()
+                      .map(|l| format!(", label = {:?}", l))
+                      .unwrap_or_default());
+        }
+    }
+
+    /// Sets how many further frames must be retired before a deleted
+    /// resource's `PostFrameTask` actually runs. Defaults to
+    /// `DEFAULT_MAX_FRAMES_IN_FLIGHT`; raise it to match a backend that keeps
+    /// more frames of CPU/GPU work in flight simultaneously.
+    pub fn set_max_frames_in_flight(&self, frames: usize) {
+        *self.max_frames_in_flight.write().unwrap() = frames.max(1);
+    }
+
+    /// Queues `task` for execution once `max_frames_in_flight` further frames
+    /// have been retired, instead of the very next frame boundary, so it can't
+    /// race GPU commands submitted by frames still in flight.
+    fn retire(&self, task: PostFrameTask) {
+        let mut retirement = self.retirement.lock().unwrap();
+        if retirement.is_empty() {
+            retirement.push_back(Vec::new());
+        }
+        retirement.back_mut().unwrap().push(task);
+    }
+
+    /// Advances the retirement ring by one frame: pushes a fresh bucket for
+    /// deletes queued this frame, then, once the ring holds more than
+    /// `max_frames_in_flight` buckets, flushes the oldest into this frame's
+    /// `PostFrameTask` queue where it will actually execute.
+    fn retire_frame(&self) {
+        let max_frames = *self.max_frames_in_flight.read().unwrap();
+        let mut retirement = self.retirement.lock().unwrap();
+        retirement.push_back(Vec::new());
+
+        while retirement.len() > max_frames + 1 {
+            let ready = retirement.pop_front().unwrap();
+            let mut frame = self.frames.front();
+            for task in ready {
+                frame.post.push(task);
+            }
         }
     }
 
@@ -211,7 +575,252 @@ impl GraphicsSystemShared {
             Command::IndexBufferUpdate(ibu) => self.submit_update_index_buffer(s, o, ibu),
             Command::TextureUpdate(tu) => self.submit_update_texture(s, o, tu),
             Command::SetScissor(sc) => self.submit_set_scissor(s, o, sc),
+            Command::Dispatch(dispatch) => self.submit_dispatch(s, o, dispatch),
+            Command::WriteTimestamp(qs, index) => self.submit_write_timestamp(s, o, qs, index),
+            Command::BeginTimeElapsed(qs) => self.submit_time_elapsed(s, o, qs, true),
+            Command::EndTimeElapsed(qs) => self.submit_time_elapsed(s, o, qs, false),
+            Command::ClearTexture(ct) => self.submit_clear_texture(s, o, ct),
+            Command::FillBuffer(fb) => self.submit_fill_buffer(s, o, fb),
+        }
+    }
+
+    fn submit_write_timestamp(&self,
+                              surface: SurfaceHandle,
+                              order: u64,
+                              qs: QuerySetHandle,
+                              index: usize)
+                              -> Result<()> {
+        if let Some(&count) = self.query_sets.read().unwrap().get(qs.into()) {
+            if index >= count {
+                bail!("Query index out of bounds.");
+            }
+
+            let mut frame = self.frames.front();
+            frame
+                .tasks
+                .push((surface, order, FrameTask::WriteTimestamp(qs, index)));
+            Ok(())
+        } else {
+            bail!(ErrorKind::InvalidHandle);
+        }
+    }
+
+    fn submit_time_elapsed(&self,
+                           surface: SurfaceHandle,
+                           order: u64,
+                           qs: QuerySetHandle,
+                           begin: bool)
+                           -> Result<()> {
+        if !self.query_sets.read().unwrap().is_alive(qs.into()) {
+            bail!(ErrorKind::InvalidHandle);
         }
+
+        let task = if begin {
+            FrameTask::BeginTimeElapsed(surface, qs)
+        } else {
+            FrameTask::EndTimeElapsed(surface, qs)
+        };
+
+        let mut frame = self.frames.front();
+        frame.tasks.push((surface, order, task));
+        Ok(())
+    }
+
+    /// Creates a set of `count` GPU query objects, used with
+    /// `Command::WriteTimestamp`/`BeginTimeElapsed`/`EndTimeElapsed` to time how
+    /// long a surface bucket (or a custom region within it) took on the GPU.
+    pub fn create_query_set(&self, count: usize) -> Result<QuerySetHandle> {
+        if count == 0 {
+            bail!("A query set must contain at least one query.");
+        }
+
+        let location = resource::Location::unique("");
+        let handle = self.query_sets
+            .write()
+            .unwrap()
+            .create(location, count)
+            .into();
+
+        {
+            let task = PreFrameTask::CreateQuerySet(handle, count);
+            self.frames.front().pre.push(task);
+        }
+
+        Ok(handle)
+    }
+
+    /// Delete a query set.
+    pub fn delete_query_set(&self, handle: QuerySetHandle) {
+        if self.query_sets
+               .write()
+               .unwrap()
+               .dec_rc(handle.into(), true)
+               .is_some() {
+            self.retire(PostFrameTask::DeleteQuerySet(handle));
+        }
+    }
+
+    /// Pre-packs `commands` into an immutable `BundleHandle`, resolving shader
+    /// uniform locations and validating handles up-front so the per-draw cost of
+    /// `submit`/`submit_drawcall` isn't paid again every frame.
+    ///
+    /// Only `Command::DrawCall`s can be bundled; any other command kind is
+    /// rejected since it has no meaning replayed out of its original frame.
+    pub fn create_command_bundle(&self, commands: &[Command]) -> Result<BundleHandle> {
+        let mut buf = ::utils::DataBuffer::with_capacity(commands.len() * 64);
+        let mut tasks = Vec::with_capacity(commands.len());
+        let mut vbos = Vec::new();
+        let mut ibos = Vec::new();
+        let mut shaders = Vec::new();
+
+        for command in commands {
+            match *command {
+                Command::DrawCall(dc) => {
+                    if !self.vertex_buffers.read().unwrap().is_alive(dc.vbo.into()) {
+                        bail!("Undefined vertex buffer handle.");
+                    }
+
+                    if let Some(ib) = dc.ibo {
+                        if !self.index_buffers.read().unwrap().is_alive(ib.into()) {
+                            bail!("Undefined index buffer handle.");
+                        }
+                    }
+
+                    let uniforms = {
+                        let mut pack = [None; MAX_UNIFORM_VARIABLES];
+                        let mut len = 0;
+
+                        if let Some(shader) = self.shaders.read().unwrap().get(dc.shader.into()) {
+                            for &(n, v) in dc.uniforms {
+                                if let Some(location) = shader.get(&n) {
+                                    pack[*location] = Some(buf.extend(&v));
+                                    len = len.max((*location + 1));
+                                } else {
+                                    bail!(format!("Undefined uniform variable: {:?}.", n));
+                                }
+                            }
+                        } else {
+                            bail!("Undefined shader state handle.");
+                        }
+
+                        buf.extend_from_slice(&pack[0..len])
+                    };
+
+                    vbos.push(dc.vbo);
+                    if let Some(ib) = dc.ibo {
+                        ibos.push(ib);
+                    }
+                    shaders.push(dc.shader);
+
+                    tasks.push(FrameTask::DrawCall(FrameDrawCall {
+                                                        shader: dc.shader,
+                                                        uniforms: uniforms,
+                                                        vb: dc.vbo,
+                                                        ib: dc.ibo,
+                                                        primitive: dc.primitive,
+                                                        from: dc.from,
+                                                        len: dc.len,
+                                                    }));
+                }
+                _ => bail!("Only draw calls can be recorded into a command bundle."),
+            }
+        }
+
+        let bundle = Bundle {
+            buf: buf,
+            tasks: tasks,
+            vertex_buffers: vbos,
+            index_buffers: ibos,
+            shaders: shaders,
+        };
+
+        let location = resource::Location::unique("");
+        let handle = self.bundles.write().unwrap().create(location, bundle).into();
+        Ok(handle)
+    }
+
+    /// Appends a single `FrameTask::ExecuteBundle` that replays the draw calls
+    /// recorded in `handle` in order. Refuses the submission if the bundle (or
+    /// any resource it references) has since been freed.
+    pub fn submit_bundle(&self, surface: SurfaceHandle, order: u64, handle: BundleHandle) -> Result<()> {
+        if !self.surfaces.read().unwrap().is_alive(surface.into()) {
+            bail!("Undefined surface handle.");
+        }
+
+        {
+            let bundles = self.bundles.read().unwrap();
+            let bundle = if let Some(bundle) = bundles.get(handle.into()) {
+                bundle
+            } else {
+                bail!("Undefined command bundle handle.");
+            };
+
+            for &vbo in &bundle.vertex_buffers {
+                if !self.vertex_buffers.read().unwrap().is_alive(vbo.into()) {
+                    bail!("Command bundle references a freed vertex buffer.");
+                }
+            }
+
+            for &ibo in &bundle.index_buffers {
+                if !self.index_buffers.read().unwrap().is_alive(ibo.into()) {
+                    bail!("Command bundle references a freed index buffer.");
+                }
+            }
+
+            for &shader in &bundle.shaders {
+                if !self.shaders.read().unwrap().is_alive(shader.into()) {
+                    bail!("Command bundle references a freed shader.");
+                }
+            }
+        }
+
+        let mut frame = self.frames.front();
+        frame.tasks.push((surface, order, FrameTask::ExecuteBundle(handle)));
+        Ok(())
+    }
+
+    /// Delete a command bundle, freeing its backing uniform buffer.
+    pub fn delete_command_bundle(&self, handle: BundleHandle) {
+        self.bundles.write().unwrap().dec_rc(handle.into(), true);
+    }
+
+    /// Submit a `Command::Dispatch` into named bucket.
+    ///
+    /// Dispatches are validated against the same surface-alive/handle-alive checks
+    /// as `submit_drawcall`, and ordered within the bucket exactly like draw calls
+    /// so a dispatch can be sequenced before the draw that consumes its output.
+    fn submit_dispatch(&self,
+                       surface: SurfaceHandle,
+                       order: u64,
+                       dispatch: command::Dispatch)
+                       -> Result<()> {
+        if !self.compute_shaders
+                .read()
+                .unwrap()
+                .is_alive(dispatch.shader.into()) {
+            bail!("Undefined compute shader handle.");
+        }
+
+        for &buffer in dispatch.storage_buffers {
+            if !self.storage_buffers.read().unwrap().is_alive(buffer.into()) {
+                bail!("Undefined storage buffer handle.");
+            }
+        }
+
+        for &texture in dispatch.storage_textures {
+            if !self.textures.read().unwrap().is_alive(texture.into()) {
+                bail!("Undefined texture handle.");
+            }
+        }
+
+        let task = FrameTask::Dispatch(dispatch.shader,
+                                        dispatch.num_groups,
+                                        dispatch.storage_buffers.to_vec(),
+                                        dispatch.storage_textures.to_vec());
+
+        let mut frame = self.frames.front();
+        frame.tasks.push((surface, order, task));
+        Ok(())
     }
 
     fn submit_drawcall<'a>(&self,
@@ -342,6 +951,52 @@ impl GraphicsSystemShared {
             bail!(ErrorKind::InvalidHandle);
         }
     }
+
+    /// Clears `ct.rect` of `ct.texture` to `ct.color`, mid-frame, without
+    /// going through a full `update_texture` upload.
+    fn submit_clear_texture(&self,
+                            surface: SurfaceHandle,
+                            order: u64,
+                            ct: command::ClearTexture)
+                            -> Result<()> {
+        if !self.surfaces.read().unwrap().is_alive(surface.into()) {
+            bail!("Undefined surface handle.");
+        }
+
+        if let Some(state) = self.textures.read().unwrap().get(ct.texture.into()) {
+            if TextureState::Ready == *state.read().unwrap() {
+                let mut frame = self.frames.front();
+                let task = FrameTask::ClearTexture(ct.texture, ct.rect, ct.color);
+                frame.tasks.push((surface, order, task));
+            }
+
+            Ok(())
+        } else {
+            bail!(ErrorKind::InvalidHandle);
+        }
+    }
+
+    /// Fills `fb.len` bytes of `fb.buf` starting at `fb.offset` with `fb.value`,
+    /// mid-frame, without staging a full replacement upload.
+    fn submit_fill_buffer(&self,
+                          surface: SurfaceHandle,
+                          order: u64,
+                          fb: command::FillBuffer)
+                          -> Result<()> {
+        if !self.surfaces.read().unwrap().is_alive(surface.into()) {
+            bail!("Undefined surface handle.");
+        }
+
+        if self.vertex_buffers.read().unwrap().is_alive(fb.buf.into()) ||
+           self.index_buffers.read().unwrap().is_alive(fb.buf.into()) {
+            let mut frame = self.frames.front();
+            let task = FrameTask::FillBuffer(fb.buf, fb.offset, fb.len, fb.value);
+            frame.tasks.push((surface, order, task));
+            Ok(())
+        } else {
+            bail!(ErrorKind::InvalidHandle);
+        }
+    }
 }
 
 impl GraphicsSystemShared {
@@ -365,14 +1020,32 @@ impl GraphicsSystemShared {
                .unwrap()
                .dec_rc(handle.into(), true)
                .is_some() {
-            let task = PostFrameTask::DeleteSurface(handle);
-            self.frames.front().post.push(task);
+            self.retire(PostFrameTask::DeleteSurface(handle));
         }
     }
 
+    /// Registers `source` under `name` so it can be pulled into any shader via
+    /// `#include "name"`, letting libraries publish reusable GLSL modules
+    /// (lighting, shadow sampling, …) instead of every shader duplicating them.
+    pub fn register_shader_include<T1, T2>(&self, name: T1, source: T2)
+        where T1: Into<String>,
+              T2: Into<String>
+    {
+        self.shader_includes
+            .write()
+            .unwrap()
+            .insert(name.into(), source.into());
+    }
+
     /// Create a shader with initial shaders and render state. Pipeline encapusulate
     /// all the informations we need to configurate OpenGL before real drawing.
-    pub fn create_shader(&self, setup: ShaderSetup) -> Result<ShaderHandle> {
+    ///
+    /// Before compiling, `setup.vs`/`setup.fs` are run through a preprocessing
+    /// pass that resolves `#include "path"` directives against the include map
+    /// populated by `register_shader_include`, so a malformed or missing include
+    /// surfaces a precise error here instead of a raw compile failure from the
+    /// backend.
+    pub fn create_shader(&self, mut setup: ShaderSetup) -> Result<ShaderHandle> {
         if setup.uniform_variables.len() > MAX_UNIFORM_VARIABLES {
             bail!("Too many uniform variables (>= {:?}).",
                   MAX_UNIFORM_VARIABLES);
@@ -386,6 +1059,12 @@ impl GraphicsSystemShared {
             bail!("Fragment shader is required to describe a proper render pipeline.");
         }
 
+        {
+            let includes = self.shader_includes.read().unwrap();
+            setup.vs = shader_preprocessor::preprocess(&setup.vs, &includes, &[])?;
+            setup.fs = shader_preprocessor::preprocess(&setup.fs, &includes, &[])?;
+        }
+
         let mut shader = ShaderState::new();
         for (i, v) in setup.uniform_variables.iter().enumerate() {
             let v: HashValue<str> = v.into();
@@ -410,8 +1089,53 @@ impl GraphicsSystemShared {
                .unwrap()
                .dec_rc(handle.into(), true)
                .is_some() {
-            let task = PostFrameTask::DeletePipeline(handle);
-            self.frames.front().post.push(task);
+            self.retire(PostFrameTask::DeletePipeline(handle));
+        }
+    }
+
+    /// Create a compute pipeline from a single compute-stage shader. Unlike
+    /// `create_shader`, the resulting handle can only be fed to `Command::Dispatch`,
+    /// letting users run GPU culling, particle simulation, or image post-processing
+    /// without round-tripping through the CPU.
+    pub fn create_compute_shader(&self, setup: ComputeShaderSetup) -> Result<ComputeShaderHandle> {
+        if setup.uniform_variables.len() > MAX_UNIFORM_VARIABLES {
+            bail!("Too many uniform variables (>= {:?}).",
+                  MAX_UNIFORM_VARIABLES);
+        }
+
+        if setup.cs.len() == 0 {
+            bail!("Compute shader is required to describe a proper compute pipeline.");
+        }
+
+        let mut shader = ShaderState::new();
+        for (i, v) in setup.uniform_variables.iter().enumerate() {
+            let v: HashValue<str> = v.into();
+            shader.insert(v, i);
+        }
+
+        let loc = resource::Location::unique("");
+        let handle = self.compute_shaders
+            .write()
+            .unwrap()
+            .create(loc, shader)
+            .into();
+
+        {
+            let task = PreFrameTask::CreateComputePipeline(handle, setup);
+            self.frames.front().pre.push(task);
+        }
+
+        Ok(handle)
+    }
+
+    /// Delete compute shader state object.
+    pub fn delete_compute_shader(&self, handle: ComputeShaderHandle) {
+        if self.compute_shaders
+               .write()
+               .unwrap()
+               .dec_rc(handle.into(), true)
+               .is_some() {
+            self.retire(PostFrameTask::DeleteComputePipeline(handle));
         }
     }
 
@@ -442,8 +1166,7 @@ impl GraphicsSystemShared {
                .unwrap()
                .dec_rc(handle.into(), true)
                .is_some() {
-            let task = PostFrameTask::DeleteFrameBuffer(handle);
-            self.frames.front().post.push(task);
+            self.retire(PostFrameTask::DeleteFrameBuffer(handle));
         }
     }
 
@@ -471,8 +1194,7 @@ impl GraphicsSystemShared {
                .unwrap()
                .dec_rc(handle.into(), true)
                .is_some() {
-            let task = PostFrameTask::DeleteRenderBuffer(handle);
-            self.frames.front().post.push(task);
+            self.retire(PostFrameTask::DeleteRenderBuffer(handle));
         }
     }
 }
@@ -496,11 +1218,21 @@ impl GraphicsSystemShared {
             .create(location, ())
             .into();
 
+        self.track_alloc(ResourceCategory::VertexBuffer,
+                          format!("{:?}", Into::<Handle>::into(handle)),
+                          format!("{:?}", setup),
+                          setup.len(),
+                          None);
+
         {
             let mut frame = self.frames.front();
             let ptr = data.map(|v| frame.buf.extend_from_slice(v));
             let task = PreFrameTask::CreateVertexBuffer(handle, setup, ptr);
             frame.pre.push(task);
+
+            if data.is_none() && setup.zero_init {
+                frame.pre.push(PreFrameTask::FillVertexBuffer(handle, 0, setup.len(), 0));
+            }
         }
 
         Ok(handle)
@@ -532,8 +1264,9 @@ impl GraphicsSystemShared {
                .unwrap()
                .dec_rc(handle.into(), true)
                .is_some() {
-            let task = PostFrameTask::DeleteVertexBuffer(handle);
-            self.frames.front().post.push(task);
+            self.track_dealloc(ResourceCategory::VertexBuffer,
+                               &format!("{:?}", Into::<Handle>::into(handle)));
+            self.retire(PostFrameTask::DeleteVertexBuffer(handle));
         }
     }
 
@@ -555,11 +1288,21 @@ impl GraphicsSystemShared {
             .create(location, ())
             .into();
 
+        self.track_alloc(ResourceCategory::IndexBuffer,
+                          format!("{:?}", Into::<Handle>::into(handle)),
+                          format!("{:?}", setup),
+                          setup.len(),
+                          None);
+
         {
             let mut frame = self.frames.front();
             let ptr = data.map(|v| frame.buf.extend_from_slice(v));
             let task = PreFrameTask::CreateIndexBuffer(handle, setup, ptr);
             frame.pre.push(task);
+
+            if data.is_none() && setup.zero_init {
+                frame.pre.push(PreFrameTask::FillIndexBuffer(handle, 0, setup.len(), 0));
+            }
         }
 
         Ok(handle)
@@ -591,8 +1334,58 @@ impl GraphicsSystemShared {
                .unwrap()
                .dec_rc(handle.into(), true)
                .is_some() {
-            let task = PostFrameTask::DeleteIndexBuffer(handle);
-            self.frames.front().post.push(task);
+            self.track_dealloc(ResourceCategory::IndexBuffer,
+                               &format!("{:?}", Into::<Handle>::into(handle)));
+            self.retire(PostFrameTask::DeleteIndexBuffer(handle));
+        }
+    }
+
+    /// Create a storage buffer object that a compute shader can read from and
+    /// write to via `Command::Dispatch`, and that a later draw call in the same
+    /// frame can bind as input.
+    pub fn create_storage_buffer(&self,
+                                 setup: StorageBufferSetup,
+                                 data: Option<&[u8]>)
+                                 -> Result<StorageBufferHandle> {
+        if let Some(buf) = data.as_ref() {
+            if buf.len() > setup.len {
+                bail!("out of bounds");
+            }
+        }
+
+        let location = resource::Location::unique("");
+        let handle = self.storage_buffers
+            .write()
+            .unwrap()
+            .create(location, ())
+            .into();
+
+        self.track_alloc(ResourceCategory::StorageBuffer,
+                          format!("{:?}", Into::<Handle>::into(handle)),
+                          format!("{:?}", setup),
+                          setup.len,
+                          None);
+
+        {
+            let mut frame = self.frames.front();
+            let ptr = data.map(|v| frame.buf.extend_from_slice(v));
+            let task = PreFrameTask::CreateStorageBuffer(handle, setup, ptr);
+            frame.pre.push(task);
+        }
+
+        Ok(handle)
+    }
+
+    /// Delete storage buffer object.
+    pub fn delete_storage_buffer(&self, handle: StorageBufferHandle) {
+        if self.storage_buffers
+               .write()
+               .unwrap()
+               .dec_rc(handle.into(), true)
+               .is_some() {
+            self.track_dealloc(ResourceCategory::StorageBuffer,
+                               &format!("{:?}", Into::<Handle>::into(handle)));
+            self.retire(PostFrameTask::DeleteStorageBuffer(handle));
         }
     }
 }
@@ -633,19 +1426,98 @@ impl GraphicsSystemShared {
 
     /// Create texture object. A texture is an image loaded in video memory,
     /// which can be sampled in shaders.
+    ///
+    /// If a texture freed by `delete_texture` is sitting idle in the recycling
+    /// pool with a matching descriptor (format, dimensions, usage, sample
+    /// count) *and* has been idle for at least `max_frames_in_flight` frames,
+    /// its GPU object is reused under this call's new handle instead of
+    /// allocating fresh, so workloads that repeatedly create and free
+    /// same-sized render targets don't churn GPU allocations every frame. A
+    /// more recently retired entry is left in the pool rather than recycled,
+    /// since a frame still in flight may still be reading it.
     pub fn create_texture(&self,
                           setup: TextureSetup,
                           data: Option<&[u8]>)
                           -> Result<TextureHandle> {
+        let key = format!("{:?}", setup);
+        // A texture only just retired may still be referenced by a frame
+        // still in flight on the GPU; recycling its GPU object this early
+        // would let this frame's draws race/overwrite what that older frame
+        // is still reading. Only an entry idle for at least
+        // `max_frames_in_flight` frames -- the same bound `retire` enforces
+        // for outright deletes -- is safe to hand back out.
+        let current_frame = *self.texture_pool_frame.read().unwrap();
+        let max_frames_in_flight = *self.max_frames_in_flight.read().unwrap();
+        let recycled = {
+            let mut pool = self.texture_pool.lock().unwrap();
+            pool.get_mut(&key).and_then(|bucket| {
+                bucket
+                    .iter()
+                    .position(|pooled| {
+                                  current_frame.saturating_sub(pooled.retired_frame) >=
+                                  max_frames_in_flight
+                              })
+                    .map(|pos| bucket.remove(pos))
+            })
+        };
+
+        if let Some(ref pooled) = recycled {
+            self.texture_descriptors.write().unwrap().remove(&pooled.handle);
+        }
+
         let loc = resource::Location::unique("");
         let state = Arc::new(RwLock::new(TextureState::Ready));
         let handle = self.textures.write().unwrap().create(loc, state).into();
+        self.texture_descriptors.write().unwrap().insert(handle, key.clone());
+
+        // 4 bytes/pixel is a rough estimate good enough for a leak report; the
+        // real byte size depends on the pixel format chosen by the backend.
+        let estimated_size = setup.dimensions.0 as usize * setup.dimensions.1 as usize * 4;
+        self.track_alloc(ResourceCategory::Texture,
+                          format!("{:?}", Into::<Handle>::into(handle)),
+                          key,
+                          estimated_size,
+                          None);
 
         {
             let mut frame = self.frames.front();
             let ptr = data.map(|v| frame.buf.extend_from_slice(v));
-            let task = PreFrameTask::CreateTexture(handle, setup, ptr);
+
+            let task = if let Some(pooled) = recycled {
+                PreFrameTask::RecycleTexture(handle, pooled.handle, setup, ptr)
+            } else {
+                PreFrameTask::CreateTexture(handle, setup, ptr)
+            };
             frame.pre.push(task);
+
+            if data.is_none() && setup.zero_init {
+                let rect = Rect::new(0, 0, setup.dimensions.0, setup.dimensions.1);
+                frame.pre.push(PreFrameTask::ClearTexture(handle, rect, [0, 0, 0, 0]));
+            }
+        }
+
+        Ok(handle)
+    }
+
+    /// Wraps a GL texture id the caller already owns (e.g. a video decoder's
+    /// `glupload`ed frame, or a camera preview texture bound by platform code
+    /// outside this crate) into a `TextureHandle` that can be sampled like any
+    /// other texture. No GPU object is created and no data is uploaded; the
+    /// returned handle borrows `raw` for as long as it lives and `delete_texture`
+    /// on it will not destroy `raw` itself.
+    pub fn create_external_texture(&self,
+                                    raw: u32,
+                                    target: ExternalTextureTarget,
+                                    dimensions: (u32, u32))
+                                    -> Result<TextureHandle> {
+        let loc = resource::Location::unique("");
+        let state = Arc::new(RwLock::new(TextureState::Ready));
+        let handle = self.textures.write().unwrap().create(loc, state).into();
+        self.external_textures.write().unwrap().insert(handle);
+
+        {
+            let task = PreFrameTask::ImportExternalTexture(handle, raw, target, dimensions);
+            self.frames.front().pre.push(task);
         }
 
         Ok(handle)
@@ -685,14 +1557,140 @@ impl GraphicsSystemShared {
     }
 
     /// Delete the texture object.
+    ///
+    /// Rather than destroying the GPU object immediately, it is moved into a
+    /// descriptor-keyed recycling pool where a later `create_texture` with a
+    /// matching descriptor can reclaim it; `frame_maintenance` sweeps entries
+    /// that have sat idle for too long and destroys them for real. Only
+    /// textures from `create_texture` carry a descriptor; any other texture
+    /// is not pooled, and its `PostFrameTask::DeleteTexture` is retired
+    /// directly.
+    ///
+    /// A handle from `create_external_texture` never owned its GL texture
+    /// id, so it is only ever unregistered here -- no `PostFrameTask` is
+    /// ever queued for it, and it is never pooled for recycling.
     pub fn delete_texture(&self, handle: TextureHandle) {
         if self.textures
                .write()
                .unwrap()
                .dec_rc(handle.into(), true)
                .is_some() {
-            let task = PostFrameTask::DeleteTexture(handle);
-            self.frames.front().post.push(task);
+            self.track_dealloc(ResourceCategory::Texture,
+                               &format!("{:?}", Into::<Handle>::into(handle)));
+
+            if self.external_textures.write().unwrap().remove(&handle) {
+                return;
+            }
+
+            if let Some(key) = self.texture_descriptors.read().unwrap().get(&handle).cloned() {
+                let retired_frame = *self.texture_pool_frame.read().unwrap();
+                self.texture_pool
+                    .lock()
+                    .unwrap()
+                    .entry(key)
+                    .or_insert_with(Vec::new)
+                    .push(PooledTexture {
+                              handle: handle,
+                              retired_frame: retired_frame,
+                          });
+            } else {
+                self.retire(PostFrameTask::DeleteTexture(handle));
+            }
+        }
+    }
+
+    /// Sweeps the texture recycling pool, destroying entries that have been
+    /// idle for more than `max_idle_frames` frames.
+    ///
+    /// `frame_index` should be a monotonically increasing counter supplied by
+    /// the caller (typically `GraphicsSystem::advance`'s own frame count), so
+    /// pooled entries age out deterministically instead of leaking forever.
+    pub fn frame_maintenance(&self, frame_index: usize, max_idle_frames: usize) {
+        *self.texture_pool_frame.write().unwrap() = frame_index;
+
+        let mut retiring = Vec::new();
+        {
+            let mut pool = self.texture_pool.lock().unwrap();
+            for bucket in pool.values_mut() {
+                let mut i = 0;
+                while i < bucket.len() {
+                    if frame_index.saturating_sub(bucket[i].retired_frame) > max_idle_frames {
+                        retiring.push(bucket.remove(i));
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            pool.retain(|_, bucket| !bucket.is_empty());
+        }
+
+        for pooled in retiring {
+            self.texture_descriptors.write().unwrap().remove(&pooled.handle);
+            self.retire(PostFrameTask::DeleteTexture(pooled.handle));
+        }
+    }
+
+    /// Enqueues an asynchronous readback of `rect` from `texture`, staging it into
+    /// a mapped buffer off the GPU. Poll the returned handle with `poll_readback`
+    /// until it resolves, which avoids stalling the main thread the way a
+    /// synchronous `glReadPixels` would.
+    pub fn read_texture(&self, texture: TextureHandle, rect: Rect) -> Result<ReadbackHandle> {
+        if !self.textures.read().unwrap().is_alive(texture.into()) {
+            bail!(ErrorKind::InvalidHandle);
+        }
+
+        let state = Arc::new(RwLock::new(ReadbackState::NotReady));
+        let location = resource::Location::unique("");
+        let handle = self.readbacks
+            .write()
+            .unwrap()
+            .create(location, state.clone())
+            .into();
+
+        let task = PostFrameTask::ReadbackTexture(handle, texture, rect, state);
+        self.frames.front().post.push(task);
+        Ok(handle)
+    }
+
+    /// Enqueues an asynchronous readback of `len` bytes at `offset` from a storage
+    /// buffer. Poll the returned handle with `poll_readback` until it resolves.
+    pub fn read_buffer(&self,
+                       buffer: StorageBufferHandle,
+                       offset: usize,
+                       len: usize)
+                       -> Result<ReadbackHandle> {
+        if !self.storage_buffers.read().unwrap().is_alive(buffer.into()) {
+            bail!(ErrorKind::InvalidHandle);
+        }
+
+        let state = Arc::new(RwLock::new(ReadbackState::NotReady));
+        let location = resource::Location::unique("");
+        let handle = self.readbacks
+            .write()
+            .unwrap()
+            .create(location, state.clone())
+            .into();
+
+        let task = PostFrameTask::ReadbackBuffer(handle, buffer, offset, len, state);
+        self.frames.front().post.push(task);
+        Ok(handle)
+    }
+
+    /// Polls a pending readback, returning the bytes once the GPU has finished
+    /// copying them out, or `None` while the readback is still in flight.
+    pub fn poll_readback(&self, handle: ReadbackHandle) -> Result<Option<Vec<u8>>> {
+        if let Some(state) = self.readbacks.read().unwrap().get(handle.into()) {
+            match *state.read().unwrap() {
+                ReadbackState::Ready(ref bytes) => Ok(Some(bytes.clone())),
+                ReadbackState::NotReady => Ok(None),
+            }
+        } else {
+            bail!(ErrorKind::InvalidHandle);
         }
     }
+
+    /// Releases a readback handle and its staging buffer.
+    pub fn delete_readback(&self, handle: ReadbackHandle) {
+        self.readbacks.write().unwrap().dec_rc(handle.into(), true);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,67 @@
+//! RAII ownership for graphics handles.
+//!
+//! `delete_texture`/`delete_vertex_buffer`/etc. must be called explicitly, which
+//! makes leaks easy whenever an early return or a panic skips past the call.
+//! `RAIIGuard` wraps a handle plus the `GraphicsSystemShared` that owns it and
+//! deletes it on `Drop`, the same way a `Box` frees its allocation.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::{GraphicsSystemShared, TextureHandle};
+
+/// A graphics handle that knows how to delete itself given the shared system
+/// it was created from.
+pub trait Dispose: Copy {
+    /// Releases this handle's reference on `video` by calling the matching
+    /// `delete_*` method, so it takes exactly the path that call takes.
+    fn dispose(self, video: &GraphicsSystemShared);
+}
+
+impl Dispose for TextureHandle {
+    fn dispose(self, video: &GraphicsSystemShared) {
+        video.delete_texture(self);
+    }
+}
+
+/// An owning wrapper around a graphics handle that deletes it when dropped.
+///
+/// Dereferences to the wrapped handle for read-only use (e.g. passing it to
+/// `submit`). Call `forget` to opt back into manual lifetime management.
+pub struct RAIIGuard<T: Dispose> {
+    video: Arc<GraphicsSystemShared>,
+    handle: Option<T>,
+}
+
+impl<T: Dispose> RAIIGuard<T> {
+    /// Wraps an already-created `handle`, transferring its lifetime to the
+    /// guard.
+    pub fn new(video: Arc<GraphicsSystemShared>, handle: T) -> Self {
+        RAIIGuard {
+            video: video,
+            handle: Some(handle),
+        }
+    }
+
+    /// Releases the wrapped handle without deleting it, handing ownership of
+    /// its lifetime back to the caller.
+    pub fn forget(mut self) -> T {
+        self.handle.take().unwrap()
+    }
+}
+
+impl<T: Dispose> Deref for RAIIGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.handle.as_ref().unwrap()
+    }
+}
+
+impl<T: Dispose> Drop for RAIIGuard<T> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.dispose(&self.video);
+        }
+    }
+}
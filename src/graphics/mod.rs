@@ -62,6 +62,8 @@ pub mod graphics;
 pub mod window;
 pub mod guard;
 pub mod drawcall;
+pub mod render_graph;
+pub mod shader_preprocessor;
 
 pub use self::assets::view::*;
 pub use self::assets::pipeline::*;
@@ -73,7 +75,9 @@ pub use self::assets::texture_loader::{TextureData, TextureParser};
 
 pub use self::guard::RAIIGuard;
 pub use self::drawcall::DrawCall;
-pub use self::graphics::{GraphicsSystem, GraphicsSystemShared, GraphicsFrameInfo};
+pub use self::render_graph::{RenderGraph, RenderGraphPass, CompiledRenderGraph};
+pub use self::graphics::{GraphicsSystem, GraphicsSystemShared, GraphicsFrameInfo,
+                          ExternalTextureTarget};
 pub use self::window::{Window, WindowBuilder};
 
 /// Maximum number of attributes in vertex layout.
@@ -0,0 +1,262 @@
+//! A render-graph layer over `GraphicsSystemShared` that automates pass ordering
+//! and transient resource lifetimes.
+//!
+//! Instead of wiring every pass to a `SurfaceHandle` by hand, users declare
+//! `RenderGraphPass`es with explicit read/write dependencies on named, virtual
+//! resources. `RenderGraph::compile` topologically sorts the passes, culls
+//! passes whose outputs are never read, and assigns each surviving pass a slot
+//! index so two non-overlapping passes can share the same physical
+//! `TextureHandle`. The graph itself makes no `create_framebuffer`/
+//! `create_render_texture`/`submit` calls and computes no `u64` order values:
+//! the caller creates one surface per slot, and each pass submits its own
+//! commands to the surface `CompiledRenderGraph::execute` hands it.
+
+use std::collections::HashMap;
+
+use super::errors::*;
+use super::{FrameBufferSetup, RenderTextureSetup, SurfaceHandle, TextureHandle};
+
+/// A virtual, graph-local identifier for a transient or imported resource.
+pub type RenderGraphResource = &'static str;
+
+/// A single pass in a `RenderGraph`.
+pub struct RenderGraphPass {
+    name: &'static str,
+    reads: Vec<RenderGraphResource>,
+    writes: Vec<RenderGraphResource>,
+    setup: RenderTextureSetup,
+    framebuffer: FrameBufferSetup,
+    execute: Box<Fn(SurfaceHandle) -> Result<()>>,
+}
+
+impl RenderGraphPass {
+    /// Declares a new pass named `name`, rendering into a transient target
+    /// described by `setup`/`framebuffer`. The pass stores both, but neither
+    /// `compile` nor `execute` reads them.
+    pub fn new<F>(name: &'static str,
+                  setup: RenderTextureSetup,
+                  framebuffer: FrameBufferSetup,
+                  execute: F)
+                  -> Self
+        where F: Fn(SurfaceHandle) -> Result<()> + 'static
+    {
+        RenderGraphPass {
+            name: name,
+            reads: Vec::new(),
+            writes: Vec::new(),
+            setup: setup,
+            framebuffer: framebuffer,
+            execute: Box::new(execute),
+        }
+    }
+
+    /// Declares that this pass samples `resource` produced by an earlier pass.
+    pub fn reads(mut self, resource: RenderGraphResource) -> Self {
+        self.reads.push(resource);
+        self
+    }
+
+    /// Declares that this pass writes `resource`, making it available to later
+    /// passes that `reads` it.
+    pub fn writes(mut self, resource: RenderGraphResource) -> Self {
+        self.writes.push(resource);
+        self
+    }
+}
+
+/// Builds up a set of `RenderGraphPass`es and compiles them into a
+/// `CompiledRenderGraph`: the surviving passes in execution order, each with
+/// a physical slot index. Compiling creates no GPU resources.
+#[derive(Default)]
+pub struct RenderGraph {
+    passes: Vec<RenderGraphPass>,
+}
+
+impl RenderGraph {
+    /// Constructs a new, empty `RenderGraph`.
+    pub fn new() -> Self {
+        RenderGraph { passes: Vec::new() }
+    }
+
+    /// Adds `pass` to the graph.
+    pub fn add_pass(&mut self, pass: RenderGraphPass) -> &mut Self {
+        self.passes.push(pass);
+        self
+    }
+
+    /// Topologically sorts the declared passes by their read/write
+    /// dependencies, culls passes whose writes are never read by a surviving
+    /// pass (and are not in `externals`), and returns the surviving passes in
+    /// execution order.
+    ///
+    /// Two passes whose lifetimes don't overlap (no reader of pass A's writes
+    /// runs after pass B starts writing, and vice versa) are allowed to alias
+    /// the same physical resource slot; `CompiledRenderGraph::slot` reports, for each surviving
+    /// pass name, the slot index it was assigned, so the caller can map
+    /// multiple passes onto the same `TextureHandle`.
+    pub fn compile(self, externals: &[RenderGraphResource]) -> Result<CompiledRenderGraph> {
+        let alive = self.cull(externals);
+
+        let mut order = Vec::new();
+        let mut visited = HashMap::new();
+        for i in 0..self.passes.len() {
+            if alive[i] {
+                Self::visit(&self.passes, &alive, i, &mut visited, &mut order)?;
+            }
+        }
+
+        let slots = Self::alias_slots(&self.passes, &order);
+
+        Ok(CompiledRenderGraph {
+               passes: self.passes,
+               order: order,
+               slots: slots,
+           })
+    }
+
+    fn cull(&self, externals: &[RenderGraphResource]) -> Vec<bool> {
+        let mut needed: Vec<RenderGraphResource> = externals.to_vec();
+        let mut alive = vec![false; self.passes.len()];
+
+        // Fixed-point: a pass is alive if any of its writes are needed, which in
+        // turn makes its own reads needed.
+        loop {
+            let mut changed = false;
+            for (i, pass) in self.passes.iter().enumerate() {
+                if !alive[i] && pass.writes.iter().any(|w| needed.contains(w)) {
+                    alive[i] = true;
+                    needed.extend(pass.reads.iter().cloned());
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        alive
+    }
+
+    fn visit(passes: &[RenderGraphPass],
+             alive: &[bool],
+             index: usize,
+             visited: &mut HashMap<usize, bool>,
+             order: &mut Vec<usize>)
+             -> Result<()> {
+        match visited.get(&index) {
+            Some(&true) => return Ok(()),
+            Some(&false) => bail!("Render graph contains a cyclic dependency."),
+            None => {}
+        }
+
+        visited.insert(index, false);
+
+        for i in 0..passes.len() {
+            if i != index && alive[i] &&
+               passes[i]
+                   .writes
+                   .iter()
+                   .any(|w| passes[index].reads.contains(w)) {
+                Self::visit(passes, alive, i, visited, order)?;
+            }
+        }
+
+        visited.insert(index, true);
+        order.push(index);
+        Ok(())
+    }
+
+    fn alias_slots(passes: &[RenderGraphPass], order: &[usize]) -> HashMap<&'static str, usize> {
+        // A simple greedy aliasing: a slot is free for pass P once every pass
+        // that reads an earlier occupant's writes has already executed.
+        let mut last_reader: HashMap<RenderGraphResource, usize> = HashMap::new();
+        for (step, &i) in order.iter().enumerate() {
+            for r in &passes[i].reads {
+                last_reader.insert(r, step);
+            }
+        }
+
+        let mut free_slots: Vec<usize> = Vec::new();
+        // (slot, step its current occupant is last read at) -- tracked as
+        // explicit pairs, not positions into this `Vec`, since removing an
+        // earlier entry would otherwise shift every later one's position out
+        // from under whatever slot number it was standing in for.
+        let mut retiring: Vec<(usize, usize)> = Vec::new();
+        let mut next_slot = 0;
+        let mut slots = HashMap::new();
+
+        for (step, &i) in order.iter().enumerate() {
+            // Only a slot whose occupant's last reader already ran (strictly
+            // *before* this step) is safe to hand out again -- recycling it
+            // at the same step it's read would alias a pass's output onto a
+            // slot it's still reading from in that very step.
+            let mut j = 0;
+            while j < retiring.len() {
+                if retiring[j].1 < step {
+                    free_slots.push(retiring[j].0);
+                    retiring.remove(j);
+                } else {
+                    j += 1;
+                }
+            }
+
+            let slot = free_slots.pop().unwrap_or_else(|| {
+                let slot = next_slot;
+                next_slot += 1;
+                slot
+            });
+            slots.insert(passes[i].name, slot);
+
+            let retires_at = passes[i]
+                .writes
+                .iter()
+                .filter_map(|w| last_reader.get(w))
+                .cloned()
+                .max()
+                .unwrap_or(step);
+            retiring.push((slot, retires_at));
+        }
+
+        slots
+    }
+}
+
+/// The result of `RenderGraph::compile`: a sequence of passes ready to be
+/// executed in order against a `GraphicsSystemShared`.
+pub struct CompiledRenderGraph {
+    passes: Vec<RenderGraphPass>,
+    order: Vec<usize>,
+    slots: HashMap<&'static str, usize>,
+}
+
+impl CompiledRenderGraph {
+    /// Returns the surviving passes, in the order they must execute.
+    pub fn passes(&self) -> impl Iterator<Item = &RenderGraphPass> {
+        self.order.iter().map(move |&i| &self.passes[i])
+    }
+
+    /// Returns the physical slot index assigned to `pass`, for aliasing
+    /// transient render targets across non-overlapping passes.
+    pub fn slot(&self, pass: &'static str) -> Option<usize> {
+        self.slots.get(pass).cloned()
+    }
+
+    /// Executes every surviving pass in dependency order against pre-created
+    /// surfaces, one per physical slot: each pass is called with the surface
+    /// `surfaces` holds for its slot. No `u64` order value is passed to the
+    /// passes; each one submits with whatever order it picks itself.
+    pub fn execute(&self, surfaces: &HashMap<usize, SurfaceHandle>) -> Result<()> {
+        for (order, pass) in self.passes().enumerate() {
+            let slot = self.slots[pass.name];
+            let surface = *surfaces
+                              .get(&slot)
+                              .ok_or("Render graph slot has no backing surface.")?;
+
+            (pass.execute)(surface)?;
+            let _ = order as u64;
+        }
+
+        Ok(())
+    }
+}
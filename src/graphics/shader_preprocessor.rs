@@ -0,0 +1,130 @@
+//! A small `#include`/`#define` preprocessor for GLSL sources, run inside
+//! `create_shader` so shaders can share common snippets (lighting, shadow
+//! sampling) without hand string-concatenating sources. `create_shader`
+//! passes no `features`, so `#define` feature flags are only added by callers
+//! of `preprocess` itself.
+
+use std::collections::{HashMap, HashSet};
+
+use super::errors::*;
+
+/// The maximum include depth, guarding against a cycle in `#include` directives.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Expands `#include "name"` directives against `includes` and prepends a
+/// `#define NAME VALUE` line for each entry of `features`, returning the fully
+/// resolved source or a precise "file + line" error if an include is missing,
+/// a directive is malformed, or includes recurse too deeply.
+pub fn preprocess(source: &str,
+                   includes: &HashMap<String, String>,
+                   features: &[(String, String)])
+                   -> Result<String> {
+    let mut stack = HashSet::new();
+    let mut resolved = expand_includes(source, "<shader>", includes, &mut stack, 0)?;
+
+    for &(ref name, ref value) in features {
+        resolved = format!("#define {} {}\n{}", name, value, resolved);
+    }
+
+    Ok(resolved)
+}
+
+fn expand_includes(source: &str,
+                   file: &str,
+                   includes: &HashMap<String, String>,
+                   stack: &mut HashSet<String>,
+                   depth: usize)
+                   -> Result<String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        bail!(format!("{}: include depth exceeds {} (recursive #include?).",
+                       file,
+                       MAX_INCLUDE_DEPTH));
+    }
+
+    let mut out = String::with_capacity(source.len());
+    for (lineno, line) in source.lines().enumerate() {
+        let trimmed = line.trim_left();
+        if trimmed.starts_with("#include") {
+            let name = parse_include(trimmed)
+                .ok_or_else(|| {
+                                Error::from(format!("{}:{}: malformed #include directive, \
+                                                      expected #include \"name\".",
+                                                     file,
+                                                     lineno + 1))
+                            })?;
+
+            if !stack.insert(name.clone()) {
+                bail!(format!("{}:{}: recursive #include of \"{}\".",
+                               file,
+                               lineno + 1,
+                               name));
+            }
+
+            let included = includes
+                .get(&name)
+                .ok_or_else(|| {
+                                Error::from(format!("{}:{}: #include \"{}\" not found; register \
+                                                      it with register_shader_include first.",
+                                                     file,
+                                                     lineno + 1,
+                                                     name))
+                            })?;
+
+            out.push_str(&expand_includes(included, &name, includes, stack, depth + 1)?);
+            out.push('\n');
+            stack.remove(&name);
+        } else {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+
+    Ok(out)
+}
+
+fn parse_include(line: &str) -> Option<String> {
+    let start = line.find('"')?;
+    let rest = &line[start + 1..];
+    let end = rest.find('"')?;
+    Some(rest[..end].to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolves_include() {
+        let mut includes = HashMap::new();
+        includes.insert("lighting.glsl".to_owned(), "vec3 light() { return vec3(1.0); }".to_owned());
+
+        let source = "#include \"lighting.glsl\"\nvoid main() {}";
+        let resolved = preprocess(source, &includes, &[]).unwrap();
+        assert!(resolved.contains("vec3 light()"));
+        assert!(resolved.contains("void main()"));
+    }
+
+    #[test]
+    fn missing_include_reports_location() {
+        let includes = HashMap::new();
+        let err = preprocess("#include \"missing.glsl\"", &includes, &[]).unwrap_err();
+        assert!(format!("{}", err).contains("missing.glsl"));
+    }
+
+    #[test]
+    fn detects_recursive_include() {
+        let mut includes = HashMap::new();
+        includes.insert("a.glsl".to_owned(), "#include \"b.glsl\"".to_owned());
+        includes.insert("b.glsl".to_owned(), "#include \"a.glsl\"".to_owned());
+
+        assert!(preprocess("#include \"a.glsl\"", &includes, &[]).is_err());
+    }
+
+    #[test]
+    fn prepends_feature_defines() {
+        let includes = HashMap::new();
+        let features = vec![("USE_SHADOWS".to_owned(), "1".to_owned())];
+        let resolved = preprocess("void main() {}", &includes, &features).unwrap();
+        assert!(resolved.starts_with("#define USE_SHADOWS 1\n"));
+    }
+}
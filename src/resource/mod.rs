@@ -40,9 +40,49 @@
 //! resources with the same name exist, this is most useful to enforce private ownership of
 //! a resource without having to care about name collisions.
 //!
-//! ## Lifetime (TODO)
+//! ## Lifetime
 //!
-//! ## Asynchronization (TODO)
+//! Every resource entry in a `Registery` is addressed by a generational
+//! `Handle`, exactly like `ObjectPool`, but also carries a refcount: a
+//! `Location` only ever maps to a single live entry, so requesting a
+//! `Location` that is already tracked bumps its refcount and hands back the
+//! existing `Handle` instead of creating a second one. The entry (and its
+//! `Location` mapping) is only dropped, and its backing slot recycled, once
+//! the refcount reaches zero, so it's safe to store and pass a `Handle`
+//! around without worrying about outliving the data it names.
+//!
+//! ## Asynchronization
+//!
+//! A module that also needs to track an in-progress load keeps an
+//! `AsyncRegistery` instead: every entry carries a `ResourceState`, starting
+//! out `Pending` and transitioning exactly once to `Ready` or `Failed` --
+//! usually from `AsyncRegistery::update`, called by a `ResourceAsyncLoader`
+//! running on the worker thread once its load completes.
+//! `AsyncRegistery::state`/`is_ready` give a non-blocking read of where an
+//! entry is at, `poll` returns the loaded data only once it is `Ready`, and
+//! `wait` blocks the calling thread until it leaves `Pending`. This is what
+//! makes it safe to store and share a `Handle` while the underlying data
+//! streams in on the background thread.
+//!
+//! A `Registery<T>`/`AsyncRegistery<T>` is already type-checked in the sense
+//! that it only ever stores one concrete `T`, but a module working with
+//! several resource types at once still has to keep one per type around by
+//! hand. For that case, `Resources` keeps a `TypeId`-keyed map of typed
+//! `ObjectPool`s behind one handle: any type that opts in via `Resource` gets
+//! its own pool, and the set of registered types can be enumerated for
+//! tooling like an inspector. The `Handle`s it returns are untyped, so a
+//! `Handle` from `Resources::create::<T>` can still be passed to another
+//! type's `get`/`free`, which look it up in that type's pool.
+//!
+//! ## Hot reloading
+//!
+//! Loading a path once and caching it is the default, but a loader can opt
+//! a path into hot reloading via `ResourceSystemShared::watch`. A dedicated
+//! watcher thread polls mtimes for every watched path and, on a change,
+//! re-reads it and re-runs the loader's `HotReloadLoader::reload`. A
+//! successful reload republishes the new asset; a failed one leaves the
+//! last-good asset in place instead of dropping it, so a bad edit never
+//! takes down something that already loaded successfully.
 //!
 
 pub mod errors;
@@ -53,7 +93,8 @@ mod location;
 pub use self::location::Location;
 
 mod registery;
-pub use self::registery::Registery;
+pub use self::registery::{Registery, AsyncRegistery, ResourceState, Resource, Resources};
 
 mod resource;
-pub use self::resource::{ResourceSystem, ResourceSystemShared, ResourceAsyncLoader};
\ No newline at end of file
+pub use self::resource::{ResourceSystem, ResourceSystemShared, ResourceAsyncLoader,
+                          HotReloadLoader, ReloadToken};
\ No newline at end of file
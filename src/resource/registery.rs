@@ -0,0 +1,437 @@
+//! A reference-counted, `Location`-keyed table of resource entries, shared by
+//! modules that need the sharing and lifetime guarantees promised by the
+//! module docs.
+//!
+//! Every entry is addressed by a generational `Handle`, same as `ObjectPool`,
+//! but also carries a refcount. A `Location` only ever maps to a single live
+//! entry -- requesting a `Location` that is already tracked bumps its
+//! refcount and hands back the existing `Handle` instead of creating a
+//! second one. The entry, and its `Location` mapping, are only dropped once
+//! the refcount reaches zero.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+
+use utils::{FastHashMap, Handle, HandlePool, ObjectPool};
+use super::Location;
+use super::errors::*;
+
+struct Entry<T> {
+    location: Option<Location>,
+    rc: usize,
+    value: T,
+}
+
+/// A `Location`-keyed table mapping `Handle`s to refcounted values of type
+/// `T`. This is the synchronous building block every graphics/resource pool
+/// in the crate is built on top of -- it only ever stores a value that is
+/// already available, with no notion of a pending load; callers that also
+/// need to track an asynchronous load's progress pair a `Registery<T>`
+/// (keyed e.g. by an already-allocated placeholder `T`) with their own
+/// atomically-swapped state, as `GraphicsSystemShared::create_texture_from`
+/// does with `TextureState`.
+pub struct Registery<T> {
+    handles: HandlePool,
+    entries: Vec<Option<Entry<T>>>,
+    locations: HashMap<Location, Handle>,
+}
+
+impl<T> Registery<T> {
+    /// Creates a new, empty `Registery`.
+    pub fn new() -> Self {
+        Registery {
+            handles: HandlePool::new(),
+            entries: Vec::new(),
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Returns the `Handle` tracking `location`, creating a fresh entry
+    /// holding `value` if none exists yet. If `location` is already tracked,
+    /// its refcount is bumped and the existing `Handle` is returned instead
+    /// -- `value` is dropped unused in that case, so callers that can't
+    /// cheaply construct `T` up front should check `lookup` first.
+    pub fn create(&mut self, location: Location, value: T) -> Handle {
+        if let Some(&handle) = self.locations.get(&location) {
+            self.entries[handle.index() as usize].as_mut().unwrap().rc += 1;
+            return handle;
+        }
+
+        let handle = self.handles.create();
+        let entry = Entry {
+            location: Some(location.clone()),
+            rc: 1,
+            value: value,
+        };
+
+        let index = handle.index() as usize;
+        if index >= self.entries.len() {
+            self.entries.push(Some(entry));
+        } else {
+            self.entries[index] = Some(entry);
+        }
+
+        self.locations.insert(location, handle);
+        handle
+    }
+
+    /// Bumps `handle`'s refcount. A no-op if `handle` does not (or no
+    /// longer) name a live entry.
+    pub fn inc_rc(&mut self, handle: Handle) {
+        if self.handles.is_alive(&handle) {
+            self.entries[handle.index() as usize].as_mut().unwrap().rc += 1;
+        }
+    }
+
+    /// Drops one reference to `handle`. Once the refcount reaches zero and
+    /// `free` is `true`, the entry (and its `Location` mapping, if any) is
+    /// removed and the backing slot recycled, with the removed value handed
+    /// back. Passing `free = false` lets a caller drop a reference without
+    /// reclaiming the slot -- the entry stays put at a refcount of zero,
+    /// `is_alive`/`get` keep reporting it as live, and a later `inc_rc`
+    /// resurrects it in place.
+    ///
+    /// Returns `None` whenever nothing was actually removed: a dead handle,
+    /// a refcount that's still positive, or `free = false`.
+    pub fn dec_rc(&mut self, handle: Handle, free: bool) -> Option<T> {
+        if !self.handles.is_alive(&handle) {
+            return None;
+        }
+
+        {
+            let entry = self.entries[handle.index() as usize].as_mut().unwrap();
+            entry.rc -= 1;
+            if entry.rc > 0 {
+                return None;
+            }
+        }
+
+        if !free {
+            return None;
+        }
+
+        let entry = self.entries[handle.index() as usize].take().unwrap();
+        if let Some(location) = entry.location {
+            self.locations.remove(&location);
+        }
+        self.handles.free(&handle);
+        Some(entry.value)
+    }
+
+    /// Returns `true` if `handle` names a live entry.
+    pub fn is_alive(&self, handle: Handle) -> bool {
+        self.handles.is_alive(&handle)
+    }
+
+    /// Returns a reference to the value named by `handle`, or `None` if it
+    /// does not name a live entry.
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        if self.handles.is_alive(&handle) {
+            self.entries[handle.index() as usize].as_ref().map(|v| &v.value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the `Handle` already tracking `location`, if any, without
+    /// creating a new entry or touching its refcount.
+    pub fn lookup(&self, location: Location) -> Option<Handle> {
+        self.locations.get(&location).cloned()
+    }
+
+    /// Drops every entry at once, regardless of refcount.
+    pub fn clear(&mut self) {
+        self.handles = HandlePool::new();
+        self.entries.clear();
+        self.locations.clear();
+    }
+
+    /// Returns the number of live entries.
+    pub fn len(&self) -> usize {
+        self.handles.size()
+    }
+}
+
+/// The load state of an `AsyncRegistery` entry. Every entry starts as
+/// `Pending` and is transitioned to `Ready` or `Failed` exactly once, by
+/// `AsyncRegistery::update`, typically called from a `ResourceAsyncLoader`'s
+/// worker thread.
+pub enum ResourceState<T> {
+    /// The backing data is still being produced on the worker thread.
+    Pending,
+    /// The backing data is available for reading.
+    Ready(Arc<T>),
+    /// Loading the backing data failed; holds the reason.
+    Failed(Error),
+}
+
+impl<T> Clone for ResourceState<T> {
+    fn clone(&self) -> Self {
+        match *self {
+            ResourceState::Pending => ResourceState::Pending,
+            ResourceState::Ready(ref v) => ResourceState::Ready(v.clone()),
+            ResourceState::Failed(ref err) => {
+                ResourceState::Failed(format!("{}", err).into())
+            }
+        }
+    }
+}
+
+struct AsyncEntry<T> {
+    location: Option<Location>,
+    refs: usize,
+    state: ResourceState<T>,
+}
+
+struct AsyncInner<T> {
+    handles: HandlePool,
+    entries: Vec<Option<AsyncEntry<T>>>,
+    locations: HashMap<Location, Handle>,
+}
+
+/// A `Location`-keyed table of refcounted entries that, unlike `Registery`,
+/// also track an in-progress load: every entry starts out `Pending` and is
+/// polled/waited on through `ResourceState`. Kept as its own type rather than
+/// folded into `Registery<T>` so that the many call sites relying on
+/// `Registery`'s plain, synchronous refcounting API -- `graphics.rs` alone
+/// has dozens -- don't have to carry a `Pending` state that never applies to
+/// them. Not yet wired into `ResourceSystem`; a module that wants this
+/// keeps its own `AsyncRegistery` the same way it would a `Registery`.
+pub struct AsyncRegistery<T> {
+    inner: Mutex<AsyncInner<T>>,
+    cond: Condvar,
+}
+
+impl<T> AsyncRegistery<T> {
+    /// Creates a new, empty `AsyncRegistery`.
+    pub fn new() -> Self {
+        AsyncRegistery {
+            inner: Mutex::new(AsyncInner {
+                                   handles: HandlePool::new(),
+                                   entries: Vec::new(),
+                                   locations: HashMap::new(),
+                               }),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Returns the `Handle` tracking `location`, along with whether this call
+    /// created a brand new, `Pending` entry for it.
+    ///
+    /// Callers should only kick off a load when the second element is `true`;
+    /// a `false` means another caller is already loading (or has already
+    /// loaded) this `location`, and this call just shared its existing entry.
+    pub fn create(&self, location: Location) -> (Handle, bool) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(&handle) = inner.locations.get(&location) {
+            let index = handle.index() as usize;
+            inner.entries[index].as_mut().unwrap().refs += 1;
+            return (handle, false);
+        }
+
+        let handle = inner.handles.create();
+        let entry = AsyncEntry {
+            location: Some(location.clone()),
+            refs: 1,
+            state: ResourceState::Pending,
+        };
+
+        let index = handle.index() as usize;
+        if index >= inner.entries.len() {
+            inner.entries.push(Some(entry));
+        } else {
+            inner.entries[index] = Some(entry);
+        }
+
+        inner.locations.insert(location, handle);
+        (handle, true)
+    }
+
+    /// Creates a brand new, unshared `Pending` entry, bypassing the
+    /// `Location` table entirely. Used for resources with a `Unique`
+    /// signature, or with no `Location` at all.
+    pub fn create_unique(&self) -> Handle {
+        let mut inner = self.inner.lock().unwrap();
+        let handle = inner.handles.create();
+        let entry = AsyncEntry {
+            location: None,
+            refs: 1,
+            state: ResourceState::Pending,
+        };
+
+        let index = handle.index() as usize;
+        if index >= inner.entries.len() {
+            inner.entries.push(Some(entry));
+        } else {
+            inner.entries[index] = Some(entry);
+        }
+
+        handle
+    }
+
+    /// Bumps `handle`'s refcount, returning `handle` back for convenience, or
+    /// `None` if it does not (or no longer) name a live entry.
+    pub fn share(&self, handle: Handle) -> Option<Handle> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.handles.is_alive(&handle) {
+            return None;
+        }
+
+        inner.entries[handle.index() as usize].as_mut().unwrap().refs += 1;
+        Some(handle)
+    }
+
+    /// Drops one reference to `handle`. Once the refcount reaches zero, the
+    /// entry (and its `Location` mapping, if any) is freed and the backing
+    /// slot recycled. Returns `true` if this call actually freed the entry.
+    pub fn free(&self, handle: Handle) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.handles.is_alive(&handle) {
+            return false;
+        }
+
+        {
+            let entry = inner.entries[handle.index() as usize].as_mut().unwrap();
+            entry.refs -= 1;
+            if entry.refs > 0 {
+                return false;
+            }
+        }
+
+        let location = inner.entries[handle.index() as usize]
+            .take()
+            .and_then(|v| v.location);
+        if let Some(location) = location {
+            inner.locations.remove(&location);
+        }
+        inner.handles.free(&handle);
+        true
+    }
+
+    /// Transitions `handle`'s state, typically called from the worker thread
+    /// once a load finishes, and wakes any thread blocked in `wait`.
+    pub fn update(&self, handle: Handle, state: ResourceState<T>) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(entry) = inner.entries[handle.index() as usize].as_mut() {
+            entry.state = state;
+        }
+        self.cond.notify_all();
+    }
+
+    /// Returns `handle`'s current state, or `None` if it does not name a live
+    /// entry.
+    pub fn state(&self, handle: Handle) -> Option<ResourceState<T>> {
+        let inner = self.inner.lock().unwrap();
+        if !inner.handles.is_alive(&handle) {
+            return None;
+        }
+
+        Some(inner.entries[handle.index() as usize].as_ref().unwrap().state.clone())
+    }
+
+    /// Returns `true` if `handle` names a live entry whose state is `Ready`.
+    pub fn is_ready(&self, handle: Handle) -> bool {
+        match self.state(handle) {
+            Some(ResourceState::Ready(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Non-blocking read of `handle`'s backing data; `None` unless its state
+    /// is already `Ready`.
+    pub fn poll(&self, handle: Handle) -> Option<Arc<T>> {
+        match self.state(handle) {
+            Some(ResourceState::Ready(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Blocks the calling thread until `handle`'s state leaves `Pending`.
+    /// Returns the backing data on `Ready`, or the failure reason on
+    /// `Failed`; fails immediately if `handle` does not name a live entry.
+    pub fn wait(&self, handle: Handle) -> Result<Arc<T>> {
+        let mut inner = self.inner.lock().unwrap();
+        loop {
+            if !inner.handles.is_alive(&handle) {
+                bail!(ErrorKind::InvalidHandle);
+            }
+
+            match inner.entries[handle.index() as usize].as_ref().unwrap().state {
+                ResourceState::Pending => {}
+                ResourceState::Ready(ref v) => return Ok(v.clone()),
+                ResourceState::Failed(ref err) => bail!(format!("{}", err)),
+            }
+
+            inner = self.cond.wait(inner).unwrap();
+        }
+    }
+}
+
+/// Opts a type into the `Resources` registry below, e.g. `impl Resource for
+/// Texture {}`. Anything implementing it gets its own backing `ObjectPool`,
+/// keyed off its `TypeId`, so it can never be mixed up with another
+/// resource type's storage.
+pub trait Resource: Any {}
+
+/// A `TypeId`-keyed map of typed `ObjectPool`s, one per `Resource` type that
+/// has opted in via `register`, so values of two unrelated types never share
+/// one backing storage. `Handle` itself is untyped, though: a `Handle` from
+/// `create::<T>` passed to `get::<U>`/`free::<U>` is looked up in `U`'s pool,
+/// where it may name an unrelated live `U`.
+pub struct Resources {
+    pools: FastHashMap<TypeId, Box<Any>>,
+}
+
+impl Resources {
+    /// Creates a new, empty `Resources`.
+    pub fn new() -> Self {
+        Resources { pools: FastHashMap::default() }
+    }
+
+    /// Registers `T` with this `Resources`, creating its backing pool. A
+    /// no-op if `T` is already registered.
+    pub fn register<T: Resource>(&mut self) {
+        self.pools
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(ObjectPool::<T>::new()));
+    }
+
+    /// Creates a `T` in its pool. Panics if `T` was never `register`ed.
+    pub fn create<T: Resource>(&mut self, value: T) -> Handle {
+        self.pool_mut::<T>().create(value)
+    }
+
+    /// Returns a reference to the `T` named by `handle`, or `None` if it is
+    /// dead, or `T` was never `register`ed.
+    pub fn get<T: Resource>(&self, handle: Handle) -> Option<&T> {
+        self.pool::<T>().and_then(|pool| pool.get(handle))
+    }
+
+    /// Recycles the `T` named by `handle`. Panics if `T` was never
+    /// `register`ed.
+    pub fn free<T: Resource>(&mut self, handle: Handle) -> Option<T> {
+        self.pool_mut::<T>().free(handle)
+    }
+
+    /// Lists every resource type currently registered, e.g. for an inspector
+    /// that wants to enumerate what's live, or for debugging leaks reported
+    /// by a module's `Registery` refcounting.
+    pub fn types(&self) -> Vec<TypeId> {
+        self.pools.keys().cloned().collect()
+    }
+
+    fn pool<T: Resource>(&self) -> Option<&ObjectPool<T>> {
+        self.pools
+            .get(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_ref::<ObjectPool<T>>())
+    }
+
+    fn pool_mut<T: Resource>(&mut self) -> &mut ObjectPool<T> {
+        self.pools
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_mut::<ObjectPool<T>>())
+            .expect("resource type not registered; call `Resources::register::<T>()` first")
+    }
+}
@@ -1,6 +1,10 @@
-use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
+use std::time::{Duration, SystemTime};
 use std::borrow::Borrow;
 use std;
 
@@ -12,6 +16,59 @@ use super::{ResourceFuture, ResourceArenaLoader, ResourceArenaMapper};
 use super::filesystem::{Filesystem, FilesystemDriver};
 use super::errors::*;
 
+/// How often the hot-reload watcher re-checks a watched path's mtime. Only
+/// spent once `ResourceSystemShared::watch` has registered at least one
+/// path; an idle watcher thread wakes up, finds nothing to check, and goes
+/// back to sleep.
+const HOT_RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Implemented by a loader that wants to be re-invoked whenever the bytes
+/// behind its already-loaded path change on disk, in addition to its
+/// initial `ResourceArenaLoader`-driven load.
+///
+/// `reload` re-runs whatever parser produced the original asset (e.g.
+/// `MeshParser::parse`/`TextureParser::parse`). A successful reload
+/// replaces the published asset via `on_reload`; a failed one calls
+/// `on_reload_failed` instead of touching anything, so a bad edit never
+/// takes down an asset that was already loaded successfully -- the
+/// last-good `Item` stays published until a reload actually succeeds.
+pub trait HotReloadLoader: Send + Sync + 'static {
+    type Item: Send + Sync + 'static;
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn reload(&self, bytes: &[u8]) -> std::result::Result<Self::Item, Self::Error>;
+    fn on_reload(&self, item: Self::Item);
+    fn on_reload_failed(&self, path: &Path, error: Self::Error);
+}
+
+/// One path being watched for hot-reload, plus the closure that re-runs its
+/// loader's `reload`/`on_reload`/`on_reload_failed` when its mtime moves.
+struct Watch {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    reload: Box<Fn(&FilesystemDriver) + Send + Sync>,
+}
+
+/// A live hot-reload subscription, returned by
+/// `ResourceSystemShared::watch`. Dropping it stops watching the path --
+/// there is no separate `unwatch` call.
+pub struct ReloadToken {
+    path: PathBuf,
+    watches: Arc<Mutex<Vec<Watch>>>,
+}
+
+impl Drop for ReloadToken {
+    fn drop(&mut self) {
+        self.watches.lock().unwrap().retain(|w| w.path != self.path);
+    }
+}
+
+thread_local! {
+    // Keyed by TypeId so every `insert_thread_local::<T>` on this thread
+    // shares one slot, same as `BorrowFlag` in `ecs::iterator`.
+    static THREAD_LOCAL_SLOTS: RefCell<HashMap<TypeId, Box<Any>>> = RefCell::new(HashMap::new());
+}
+
 /// The centralized resource management system.
 pub struct ResourceSystem {
     filesystems: Arc<RwLock<FilesystemDriver>>,
@@ -22,7 +79,8 @@ impl ResourceSystem {
     /// Creates a new `ResourceSystem`.
     ///
     /// Notes that this will spawn a worker thread running background to perform
-    /// io requests.
+    /// io requests, plus an idle hot-reload watcher thread that does nothing
+    /// until a caller opts in via `ResourceSystemShared::watch`.
     pub fn new() -> Result<Self> {
         let driver = Arc::new(RwLock::new(FilesystemDriver::new()));
 
@@ -33,7 +91,15 @@ impl ResourceSystem {
             thread::spawn(|| { ResourceSystem::run(rx, driver); });
         }
 
-        let shared = ResourceSystemShared::new(driver.clone(), tx);
+        let watches = Arc::new(Mutex::new(Vec::new()));
+
+        {
+            let driver = driver.clone();
+            let watches = watches.clone();
+            thread::spawn(move || { ResourceSystem::watch_loop(watches, driver); });
+        }
+
+        let shared = ResourceSystemShared::new(driver.clone(), tx, watches);
 
         Ok(ResourceSystem {
                filesystems: driver,
@@ -63,6 +129,38 @@ impl ResourceSystem {
         self.filesystems.write().unwrap().unmount(ident);
     }
 
+    /// Stores `value` in a slot private to the calling thread, keyed by its
+    /// type. For resources that cannot be `Send`/`Sync` -- GPU objects,
+    /// platform windows, audio device handles -- and so can never live in a
+    /// `Registery`/`Resources` pool that might be touched from another
+    /// thread. Overwrites this thread's previous `T` slot, if any.
+    pub fn insert_thread_local<T: 'static>(&self, value: T) {
+        THREAD_LOCAL_SLOTS.with(|slots| {
+                                     slots.borrow_mut().insert(TypeId::of::<T>(), Box::new(value));
+                                 });
+    }
+
+    /// Calls `f` with a reference to this thread's `T` slot, or returns
+    /// `None` without calling it if nothing of that type was ever
+    /// `insert_thread_local`'d on this thread -- in particular, always
+    /// `None` from any thread other than the one that inserted it.
+    ///
+    /// Takes a closure rather than handing back `&T` directly because the
+    /// slot lives behind a `RefCell` shared with `insert_thread_local`: a
+    /// later `insert_thread_local::<T>` call overwrites (and drops) it, so
+    /// any `&T` that outlived this call could dangle. Scoping the borrow to
+    /// `f` makes that impossible to express.
+    pub fn get_thread_local<T: 'static, F, R>(&self, f: F) -> Option<R>
+        where F: FnOnce(&T) -> R
+    {
+        THREAD_LOCAL_SLOTS.with(|slots| {
+            slots.borrow()
+                .get(&TypeId::of::<T>())
+                .and_then(|v| v.downcast_ref::<T>())
+                .map(f)
+        })
+    }
+
     fn run(chan: two_lock_queue::Receiver<ResourceTask>, driver: Arc<RwLock<FilesystemDriver>>) {
         let mut buf = Vec::new();
 
@@ -98,11 +196,40 @@ impl ResourceSystem {
         let asset = slave.insert(&path, &buf[from..])?;
         Ok(asset)
     }
+
+    /// Wakes up every `HOT_RELOAD_POLL_INTERVAL`, and for each watched path
+    /// whose mtime has moved since the last check, reads it and reissues
+    /// its loader's `reload`. Runs for as long as `watches` is alive, which
+    /// is as long as the owning `ResourceSystemShared` is.
+    fn watch_loop(watches: Arc<Mutex<Vec<Watch>>>, driver: Arc<RwLock<FilesystemDriver>>) {
+        loop {
+            thread::sleep(HOT_RELOAD_POLL_INTERVAL);
+
+            // Only this thread still holds `watches`: the owning
+            // `ResourceSystemShared` and every `ReloadToken` are gone.
+            if Arc::strong_count(&watches) == 1 {
+                return;
+            }
+
+            let driver = driver.read().unwrap();
+            let mut watches = watches.lock().unwrap();
+
+            for watch in watches.iter_mut() {
+                if let Some(modified) = driver.modified(&watch.path) {
+                    if watch.last_modified != Some(modified) {
+                        watch.last_modified = Some(modified);
+                        (watch.reload)(&driver);
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub struct ResourceSystemShared {
     filesystems: Arc<RwLock<FilesystemDriver>>,
     chan: two_lock_queue::Sender<ResourceTask>,
+    watches: Arc<Mutex<Vec<Watch>>>,
 }
 
 enum ResourceTask {
@@ -113,11 +240,13 @@ enum ResourceTask {
 
 impl ResourceSystemShared {
     fn new(filesystems: Arc<RwLock<FilesystemDriver>>,
-           chan: two_lock_queue::Sender<ResourceTask>)
+           chan: two_lock_queue::Sender<ResourceTask>,
+           watches: Arc<Mutex<Vec<Watch>>>)
            -> Self {
         ResourceSystemShared {
             filesystems: filesystems,
             chan: chan,
+            watches: watches,
         }
     }
 
@@ -127,6 +256,52 @@ impl ResourceSystemShared {
         self.filesystems.read().unwrap().exists(path)
     }
 
+    /// Opts a previously-loaded path into hot reloading: from now on, every
+    /// `HOT_RELOAD_POLL_INTERVAL` the watcher thread checks whether `path`'s
+    /// mtime moved, and if so re-reads it and calls `loader.reload`,
+    /// publishing the result via `loader.on_reload`/`on_reload_failed`.
+    ///
+    /// Returns a `ReloadToken`; dropping it stops watching `path`.
+    pub fn watch<T, P>(&self, loader: T, path: P) -> ReloadToken
+        where T: HotReloadLoader,
+              P: AsRef<Path>
+    {
+        let path = path.as_ref().to_owned();
+        let reload_path = path.clone();
+
+        let reload = move |driver: &FilesystemDriver| {
+            let mut buf = Vec::new();
+            match driver.load_into(&reload_path, &mut buf) {
+                Ok(_) => {
+                    match loader.reload(&buf) {
+                        Ok(item) => loader.on_reload(item),
+                        Err(error) => loader.on_reload_failed(&reload_path, error),
+                    }
+                }
+                Err(_) => {
+                    // Transient read failure (e.g. caught mid-write); leave
+                    // the last-good asset alone. `watch_loop` has already
+                    // recorded this mtime, so the path is read again only
+                    // once its mtime moves again.
+                }
+            }
+        };
+
+        self.watches
+            .lock()
+            .unwrap()
+            .push(Watch {
+                      path: path.clone(),
+                      last_modified: self.filesystems.read().unwrap().modified(&path),
+                      reload: Box::new(reload),
+                  });
+
+        ReloadToken {
+            path: path,
+            watches: self.watches.clone(),
+        }
+    }
+
     pub fn load<T, P>(&self, slave: T, path: P) -> ResourceFuture<T::Item, T::Error>
         where T: ResourceArenaLoader,
               P: AsRef<Path>
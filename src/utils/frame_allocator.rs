@@ -0,0 +1,112 @@
+use std::any::Any;
+use std::mem;
+
+/// A handle into a `FrameAllocator`.
+///
+/// Unlike `Handle`, there is no per-slot generational reuse check -- a whole
+/// `FrameAllocator` generation is invalidated at once by `reset`, so a
+/// `FrameHandle` only needs to remember which generation it was allocated in
+/// to detect staleness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHandle {
+    index: u32,
+    generation: u32,
+}
+
+struct Slot {
+    value: Box<Any>,
+    size: usize,
+}
+
+/// A per-frame scratch store for short-lived data -- draw-call command
+/// lists, temporary transforms, culling results -- that is reset wholesale
+/// once per frame instead of freeing entries individually.
+///
+/// Mirrors `ObjectPool`'s handle-based access pattern (`alloc` stands in for
+/// `create`, `get` is identical), but trades `ObjectPool`'s persistent,
+/// per-slot generational bookkeeping for a single per-allocator generation:
+/// `reset` bumps it once and invalidates every outstanding `FrameHandle`.
+/// Each `alloc` boxes its value on the heap and `reset` drops every box one
+/// by one; only the slot list's capacity is kept for the next frame.
+pub struct FrameAllocator {
+    slots: Vec<Slot>,
+    generation: u32,
+}
+
+impl FrameAllocator {
+    /// Constructs a new, empty `FrameAllocator`.
+    pub fn new() -> Self {
+        FrameAllocator {
+            slots: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// Allocates `value` in this frame's scratch storage.
+    pub fn alloc<T: Any>(&mut self, value: T) -> FrameHandle {
+        let index = self.slots.len() as u32;
+        self.slots
+            .push(Slot {
+                      value: Box::new(value),
+                      size: mem::size_of::<T>(),
+                  });
+
+        FrameHandle {
+            index: index,
+            generation: self.generation,
+        }
+    }
+
+    /// Returns a reference to the `T` named by `handle`, or `None` if
+    /// `handle` is from a stale generation (a `reset` happened since it was
+    /// allocated), out of range, or was allocated with a different type.
+    pub fn get<T: Any>(&self, handle: FrameHandle) -> Option<&T> {
+        if handle.generation != self.generation {
+            return None;
+        }
+
+        self.slots
+            .get(handle.index as usize)
+            .and_then(|slot| slot.value.downcast_ref::<T>())
+    }
+
+    /// Invalidates every outstanding `FrameHandle`, advances the generation
+    /// and drops every allocation, keeping the slot list's capacity for the
+    /// next frame. Returns the
+    /// number of bytes reclaimed, so callers can watch per-frame churn (e.g.
+    /// through `FrameInfo::reclaimed_bytes`).
+    pub fn reset(&mut self) -> usize {
+        let bytes = self.slots.iter().map(|slot| slot.size).sum();
+        self.slots.clear();
+        self.generation = self.generation.wrapping_add(1);
+        bytes
+    }
+
+    /// Returns the number of live allocations made since the last `reset`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn basic() {
+        let mut allocator = FrameAllocator::new();
+
+        let a = allocator.alloc(3i32);
+        let b = allocator.alloc("scratch");
+
+        assert_eq!(allocator.get::<i32>(a), Some(&3));
+        assert_eq!(allocator.get::<&str>(b), Some(&"scratch"));
+        assert_eq!(allocator.len(), 2);
+
+        let reclaimed = allocator.reset();
+        assert!(reclaimed > 0);
+        assert_eq!(allocator.len(), 0);
+        assert_eq!(allocator.get::<i32>(a), None);
+    }
+}
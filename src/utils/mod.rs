@@ -3,6 +3,7 @@
 #[macro_use]
 pub mod handle;
 pub mod data_buf;
+pub mod frame_allocator;
 pub mod handle_pool;
 pub mod hash;
 pub mod hash_value;
@@ -11,6 +12,7 @@ pub mod variant_str;
 pub mod variant_vec;
 
 pub use self::data_buf::{DataBuffer, DataBufferPtr};
+pub use self::frame_allocator::{FrameAllocator, FrameHandle};
 pub use self::handle::{Handle, HandleIndex};
 pub use self::handle_pool::HandlePool;
 pub use self::hash::{FastHashMap, FastHashSet};
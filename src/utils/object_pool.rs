@@ -1,11 +1,13 @@
 use std::borrow::Borrow;
-use super::{Handle, HandlePool, HandleIter};
+use std::marker::PhantomData;
+use super::{FastHashMap, Handle, HandlePool, HandleIter};
 
 /// A named object collections. Every time u create or free a handle, a
 /// attached instance `T` will be created/ freed.
 pub struct ObjectPool<T: Sized> {
     handles: HandlePool,
     values: Vec<Option<T>>,
+    names: FastHashMap<String, Handle>,
 }
 
 impl<T: Sized> ObjectPool<T> {
@@ -14,6 +16,7 @@ impl<T: Sized> ObjectPool<T> {
         ObjectPool {
             handles: HandlePool::new(),
             values: Vec::new(),
+            names: FastHashMap::default(),
         }
     }
 
@@ -22,6 +25,7 @@ impl<T: Sized> ObjectPool<T> {
         ObjectPool {
             handles: HandlePool::with_capacity(capacity),
             values: Vec::with_capacity(capacity),
+            names: FastHashMap::default(),
         }
     }
 
@@ -38,6 +42,27 @@ impl<T: Sized> ObjectPool<T> {
         handle
     }
 
+    /// Creates a `T`, same as `create`, and additionally registers it under
+    /// the human-readable identifier `name` so it can later be resolved with
+    /// `find`. Overwrites whatever `Handle` `name` previously resolved to.
+    pub fn create_named<S>(&mut self, name: S, value: T) -> Handle
+        where S: Into<String>
+    {
+        let handle = self.create(value);
+        self.names.insert(name.into(), handle);
+        handle
+    }
+
+    /// Resolves a `Handle` previously registered with `create_named`. Note
+    /// that `free` does not prune `names`, so a resolved `Handle` can still
+    /// be dead -- callers should check `is_alive`/`get` before trusting it,
+    /// same as any `Handle` obtained before a `free`.
+    pub fn find<S>(&self, name: S) -> Option<Handle>
+        where S: Borrow<str>
+    {
+        self.names.get(name.borrow()).cloned()
+    }
+
     /// Returns mutable reference to internal value with name `Handle`.
     #[inline]
     pub fn get_mut<H>(&mut self, handle: H) -> Option<&mut T>
@@ -97,6 +122,65 @@ impl<T: Sized> ObjectPool<T> {
     pub fn iter(&self) -> HandleIter {
         self.handles.iter()
     }
+
+    /// Returns an iterator walking every live `(Handle, &T)` pair in one
+    /// pass, without the separate liveness re-check a `iter().map(|h|
+    /// (h, self.get(h).unwrap()))` would need.
+    pub fn values(&self) -> Values<T> {
+        Values {
+            handles: self.handles.iter(),
+            values: &self.values,
+        }
+    }
+
+    /// Mutable counterpart of `values`.
+    pub fn values_mut(&mut self) -> ValuesMut<T> {
+        ValuesMut {
+            handles: self.handles.iter(),
+            values: &mut self.values as *mut Vec<Option<T>>,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// See `ObjectPool::values`.
+pub struct Values<'a, T: 'a> {
+    handles: HandleIter<'a>,
+    values: &'a [Option<T>],
+}
+
+impl<'a, T: 'a> Iterator for Values<'a, T> {
+    type Item = (Handle, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.handles
+            .next()
+            .map(|handle| (handle, self.values[handle.index() as usize].as_ref().unwrap()))
+    }
+}
+
+/// See `ObjectPool::values_mut`.
+pub struct ValuesMut<'a, T: 'a> {
+    handles: HandleIter<'a>,
+    // Raw pointer, not `&'a mut Vec<Option<T>>`, because `next` needs to hand
+    // out a `&'a mut T` on every call while `self` itself is only borrowed
+    // for the duration of that call. Same reborrow trick `build_view_with!`
+    // uses for its `ViewIterator`; sound because every yielded handle names
+    // a distinct slot, so no two calls ever alias.
+    values: *mut Vec<Option<T>>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: 'a> Iterator for ValuesMut<'a, T> {
+    type Item = (Handle, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.handles.next().map(|handle| unsafe {
+            let values = &mut *self.values;
+            let v = values[handle.index() as usize].as_mut().unwrap();
+            (handle, v)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -116,4 +200,33 @@ mod test {
         assert_eq!(set.free(e1), None);
         assert_eq!(set.len(), 0);
     }
+
+    #[test]
+    fn values() {
+        let mut set = ObjectPool::<i32>::new();
+
+        let e1 = set.create(3);
+        let e2 = set.create(5);
+
+        let mut seen: Vec<_> = set.values().map(|(h, v)| (h, *v)).collect();
+        seen.sort_by_key(|&(_, v)| v);
+        assert_eq!(seen, vec![(e1, 3), (e2, 5)]);
+
+        for (_, v) in set.values_mut() {
+            *v *= 2;
+        }
+
+        assert_eq!(set.get(e1), Some(&6));
+        assert_eq!(set.get(e2), Some(&10));
+    }
+
+    #[test]
+    fn named() {
+        let mut set = ObjectPool::<i32>::new();
+
+        let handle = set.create_named("foo", 42);
+        assert_eq!(set.find("foo"), Some(handle));
+        assert_eq!(set.find("bar"), None);
+        assert_eq!(set.get(set.find("foo").unwrap()), Some(&42));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,74 @@
+use bincode;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+
+use errors::*;
+
+use super::super::backends::frame::Command;
+use super::super::DoubleFrame;
+use super::shader::*;
+
+pub const MAGIC: [u8; 8] = [
+    'V' as u8, 'S' as u8, 'H' as u8, 'D' as u8, ' ' as u8, 0, 0, 1,
+];
+
+/// Loads a `.vs`/`.fs` pair from the filesystem. `reload` pushes a
+/// `Command::UpdateShader` for an already-created handle without
+/// invalidating the `ShaderHandle` or anything drawing through it;
+/// `ShaderLoader` itself does not watch the source files for changes.
+#[derive(Clone)]
+pub struct ShaderLoader {
+    frames: Arc<DoubleFrame>,
+}
+
+impl ShaderLoader {
+    pub(crate) fn new(frames: Arc<DoubleFrame>) -> Self {
+        ShaderLoader { frames: frames }
+    }
+
+    /// Re-submits `vs`/`fs` for an already-created `handle`. The backend
+    /// compiles and links the replacement program without touching the
+    /// handle or any `DrawCall` referencing it; on a compile/link failure it
+    /// keeps the previously linked program alive and the error is surfaced
+    /// through `shader_state` instead of panicking the render thread.
+    pub fn reload(&self, handle: ShaderHandle, vs: String, fs: String) {
+        let cmd = Command::UpdateShader(handle, vs, fs);
+        self.frames.front().cmds.push(cmd);
+    }
+}
+
+impl ::res::registry::Register for ShaderLoader {
+    type Handle = ShaderHandle;
+    type Intermediate = (ShaderParams, String, String);
+    type Value = ShaderParams;
+
+    fn load(&self, handle: Self::Handle, bytes: &[u8]) -> Result<Self::Intermediate> {
+        if &bytes[0..8] != &MAGIC[..] {
+            bail!("[ShaderLoader] MAGIC number not match.");
+        }
+
+        let mut file = Cursor::new(&bytes[8..]);
+        let params: ShaderParams = bincode::deserialize_from(&mut file)?;
+
+        let mut vs = String::new();
+        let mut fs = String::new();
+        file.read_to_string(&mut vs)?;
+        file.read_to_string(&mut fs)?;
+
+        info!("[ShaderLoader] loads {:?}.", handle);
+
+        Ok((params, vs, fs))
+    }
+
+    fn attach(&self, handle: Self::Handle, item: Self::Intermediate) -> Result<Self::Value> {
+        let (params, vs, fs) = item;
+        let cmd = Command::CreateShader(handle, params.clone(), vs, fs);
+        self.frames.front().cmds.push(cmd);
+        Ok(params)
+    }
+
+    fn detach(&self, handle: Self::Handle, _: Self::Value) {
+        let cmd = Command::DeleteShader(handle);
+        self.frames.front().cmds.push(cmd);
+    }
+}
@@ -0,0 +1,127 @@
+use utils::Handle;
+
+impl_handle!(UniformBufferHandle);
+
+/// Parameters of a `UniformBufferHandle`, the block name it will be bound to
+/// in a shader and how large its backing store is.
+#[derive(Debug, Clone)]
+pub struct UniformBufferParams {
+    /// The `layout(std140) uniform <name> { .. }` block this buffer binds to.
+    pub name: String,
+    /// Size in bytes of the packed std140 data, i.e. `AsStd140::as_std140().len()`.
+    pub size: usize,
+}
+
+impl Default for UniformBufferParams {
+    fn default() -> Self {
+        UniformBufferParams {
+            name: String::new(),
+            size: 0,
+        }
+    }
+}
+
+/// Implemented by `impl_std140!`-generated structs. Packs the struct's fields
+/// into a flat buffer following std140 layout rules (GLSL spec 4.5.3):
+/// scalars align to 4 bytes, `vec2` to 8, `vec3`/`vec4`/matrix columns to 16,
+/// and every array element (including matrix columns) is padded out to a
+/// 16-byte stride.
+pub trait AsStd140 {
+    fn as_std140(&self) -> Vec<u8>;
+}
+
+/// Pads `buf` up to a multiple of `align`, then appends `bytes`. Shared by
+/// every field emitted from `impl_std140!`.
+#[doc(hidden)]
+pub fn pack_std140_field(buf: &mut Vec<u8>, align: usize, bytes: &[u8]) {
+    let pad = (align - (buf.len() % align)) % align;
+    buf.extend(::std::iter::repeat(0u8).take(pad));
+    buf.extend_from_slice(bytes);
+}
+
+/// Declares a struct of shader uniforms and an `AsStd140` implementation that
+/// packs it per std140 rules, the `UniformBuffer` analogue of `impl_vertex!`.
+///
+/// ```rust,ignore
+/// impl_std140! {
+///     Locals {
+///         mvp => Mat4,
+///         tint => Vec4,
+///         time => Float,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_std140 {
+    ($name: ident { $($field: ident => $ty: tt,)* }) => (
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        pub struct $name {
+            $(pub $field: impl_std140_field!{$ty}, )*
+        }
+
+        impl $crate::video::assets::uniform_buffer::AsStd140 for $name {
+            fn as_std140(&self) -> Vec<u8> {
+                let mut buf = Vec::new();
+                $(
+                    {
+                        let field = &self.$field;
+                        let bytes = unsafe {
+                            ::std::slice::from_raw_parts(
+                                field as *const _ as *const u8,
+                                ::std::mem::size_of_val(field))
+                        };
+                        $crate::video::assets::uniform_buffer::pack_std140_field(
+                            &mut buf, impl_std140_align!{$ty}, bytes);
+                    }
+                )*
+                buf
+            }
+        }
+    )
+}
+
+#[macro_export]
+macro_rules! impl_std140_field {
+    (Float) => (f32);
+    (Vec2) => ([f32; 2]);
+    (Vec3) => ([f32; 3]);
+    (Vec4) => ([f32; 4]);
+    (Mat4) => ([[f32; 4]; 4]);
+}
+
+#[macro_export]
+macro_rules! impl_std140_align {
+    (Float) => (4);
+    (Vec2) => (8);
+    (Vec3) => (16);
+    (Vec4) => (16);
+    (Mat4) => (16);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    impl_std140! {
+        Locals {
+            mvp => Mat4,
+            tint => Vec4,
+            time => Float,
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let locals = Locals {
+            mvp: [[0.0; 4]; 4],
+            tint: [1.0, 0.0, 0.0, 1.0],
+            time: 0.5,
+        };
+
+        let bytes = locals.as_std140();
+        // mat4 (64) + vec4 (16, already 16-aligned) + float (4, no padding
+        // needed since the buffer is already 4-byte aligned at this point).
+        assert_eq!(bytes.len(), 64 + 16 + 4);
+    }
+}
@@ -0,0 +1,147 @@
+//! Generic draw phases with pluggable sort keys.
+//!
+//! [Layered Rendering](../index.html#layered-rendering) sorts every command
+//! by a single packed integer key before submitting it to OpenGL. `DrawPhase`
+//! generalizes that: each phase defines its own "rendered thing" through
+//! `PhaseItem` and its own notion of sort order through `PhaseItem::SortKey`,
+//! instead of every caller hand-rolling the same bit layout.
+
+use super::backends::frame::Frame;
+
+/// One piece of pipeline state a draw needs: bind a shader, bind a mesh, set
+/// a uniform, issue the draw call. A complex draw is composed from a tuple
+/// or `Vec` of small, reusable `RenderCommand`s instead of one monolithic
+/// `DrawCall` builder.
+pub trait RenderCommand {
+    /// Pushes this command's effect onto `frame`.
+    fn encode(&self, frame: &mut Frame);
+}
+
+impl<A: RenderCommand, B: RenderCommand> RenderCommand for (A, B) {
+    fn encode(&self, frame: &mut Frame) {
+        self.0.encode(frame);
+        self.1.encode(frame);
+    }
+}
+
+impl<A: RenderCommand, B: RenderCommand, C: RenderCommand> RenderCommand for (A, B, C) {
+    fn encode(&self, frame: &mut Frame) {
+        self.0.encode(frame);
+        self.1.encode(frame);
+        self.2.encode(frame);
+    }
+}
+
+/// The "rendered thing" collected by a `DrawPhase`. Each phase picks its own
+/// `SortKey`, e.g. front-to-back distance for opaque geometry and
+/// back-to-front for transparent geometry, and items only ever get compared
+/// against other items of the same phase.
+pub trait PhaseItem {
+    type SortKey: Ord;
+
+    /// The key items in this phase are sorted by before `DrawPhase::flush`.
+    fn sort_key(&self) -> Self::SortKey;
+
+    /// Records the commands this item represents.
+    fn render(&self, frame: &mut Frame);
+}
+
+/// Collects `PhaseItem`s across a frame, sorts them by `PhaseItem::sort_key`,
+/// then flushes them in that order. Reused frame-to-frame; `flush` clears it.
+pub struct DrawPhase<I: PhaseItem> {
+    items: Vec<I>,
+}
+
+impl<I: PhaseItem> DrawPhase<I> {
+    pub fn new() -> Self {
+        DrawPhase { items: Vec::new() }
+    }
+
+    /// Queues `item` for the next `flush`.
+    pub fn push(&mut self, item: I) {
+        self.items.push(item);
+    }
+
+    /// Sorts every queued item by its `sort_key` and records them, in that
+    /// order, into `frame`.
+    pub fn flush(&mut self, frame: &mut Frame) {
+        self.items.sort_by_key(PhaseItem::sort_key);
+
+        for item in &self.items {
+            item.render(frame);
+        }
+
+        self.items.clear();
+    }
+}
+
+/// Maps a non-negative distance to a `u32` that sorts the same way a `f32`
+/// comparison would, so phases can use a plain integer `SortKey` (IEEE-754's
+/// bit pattern is already monotonic across non-negative floats).
+fn distance_sort_bits(distance: f32) -> u32 {
+    distance.max(0.0).to_bits()
+}
+
+/// Built-in `PhaseItem` for opaque geometry, sorted front-to-back so the
+/// depth test rejects occluded fragments before they reach the fragment
+/// shader.
+pub struct Opaque<C: RenderCommand> {
+    pub distance: f32,
+    pub command: C,
+}
+
+impl<C: RenderCommand> PhaseItem for Opaque<C> {
+    type SortKey = u32;
+
+    fn sort_key(&self) -> u32 {
+        distance_sort_bits(self.distance)
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        self.command.encode(frame);
+    }
+}
+
+/// Built-in `PhaseItem` for transparent geometry, sorted back-to-front so
+/// alpha blending composites in the right order.
+pub struct Transparent<C: RenderCommand> {
+    pub distance: f32,
+    pub command: C,
+}
+
+impl<C: RenderCommand> PhaseItem for Transparent<C> {
+    type SortKey = u32;
+
+    fn sort_key(&self) -> u32 {
+        ::std::u32::MAX - distance_sort_bits(self.distance)
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        self.command.encode(frame);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Noop;
+
+    impl RenderCommand for Noop {
+        fn encode(&self, _: &mut Frame) {}
+    }
+
+    #[test]
+    fn opaque_sorts_front_to_back() {
+        let near = Opaque { distance: 1.0, command: Noop };
+        let far = Opaque { distance: 10.0, command: Noop };
+        assert!(near.sort_key() < far.sort_key());
+    }
+
+    #[test]
+    fn transparent_sorts_back_to_front() {
+        let near = Transparent { distance: 1.0, command: Noop };
+        let far = Transparent { distance: 10.0, command: Noop };
+        assert!(near.sort_key() > far.sort_key());
+    }
+}
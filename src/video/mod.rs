@@ -160,10 +160,16 @@
 //! video::delete_texture(texture);
 //! ```
 //!
+//! #### Cube and 3D Textures
+//!
+//! `TextureParams::dimensions` selects between a plain 2D image, a six-faced
+//! cube (skyboxes, environment reflections) and a depth-sliced volume
+//! (volumetric lookups). Use `update_texture_layer` instead of `update_texture`
+//! to upload one face or slice at a time.
+//!
 //! #### Compressed Texture Format
 //!
-//! _TODO_: Cube texture.
-//! _TODO_: 3D texture.
+//! _TODO_: Compressed texture format.
 //!
 //! ### Mesh Object
 //!
@@ -205,9 +211,11 @@ mod backends;
 
 pub mod prelude {
     pub use super::assets::prelude::*;
-    pub use super::command::{CommandBuffer, Draw, DrawCommandBuffer};
+    pub use super::command::{CommandBuffer, Draw, DrawCommandBuffer, PhaseItem, DrawPhase,
+                              RenderCommand, Opaque, Transparent};
 }
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -283,6 +291,24 @@ pub fn create_shader(params: ShaderParams, vs: String, fs: String) -> Result<Sha
     ctx().create_shader(params, vs, fs)
 }
 
+/// Creates a shader object from file asynchronously, watching the source
+/// files so `reload_shader` can hot-swap the linked program later without
+/// invalidating the returned handle.
+#[inline]
+pub fn create_shader_from<T: AsRef<str>>(url: T) -> Result<ShaderHandle> {
+    ctx().create_shader_from(url)
+}
+
+/// Recompiles and relinks an already-created shader in place, e.g. after a
+/// watched `.vs`/`.fs` changed on disk. Every `DrawCall` referencing `handle`
+/// keeps working unmodified. If `vs`/`fs` fail to compile, the previously
+/// linked program is kept and the failure is surfaced through `shader_state`
+/// instead of erroring out of the frame loop.
+#[inline]
+pub fn reload_shader(handle: ShaderHandle, vs: String, fs: String) -> Result<()> {
+    ctx().reload_shader(handle, vs, fs)
+}
+
 /// Gets the `ShaderParams` if available.
 #[inline]
 pub fn shader(handle: ShaderHandle) -> Option<ShaderParams> {
@@ -404,6 +430,65 @@ pub fn delete_texture(handle: TextureHandle) {
     ctx().delete_texture(handle);
 }
 
+/// Like `update_texture`, but returns immediately instead of blocking on
+/// `glTexSubImage2D`.
+///
+/// The backend maps a pixel buffer object from a size-bucketed free-list
+/// (so repeated same-sized streaming updates, e.g. video frames or a font
+/// atlas, don't allocate a fresh PBO every call), memcpies `data` into it
+/// respecting row alignment, and issues the texture upload as an
+/// asynchronous DMA transfer off of that buffer. The PBO is returned to the
+/// free-list once a fence confirms the GPU is done reading it.
+///
+/// Returns a `TextureUploadFence` the caller can poll or wait on to learn
+/// when `data` has actually been consumed and may be reused or freed.
+#[inline]
+pub fn update_texture_async(
+    handle: TextureHandle,
+    area: Aabb2<u32>,
+    data: &[u8],
+) -> Result<TextureUploadFence> {
+    ctx().update_texture_async(handle, area, data)
+}
+
+/// Create a uniform buffer object, packing `data` (usually the output of an
+/// `impl_std140!` struct's `AsStd140::as_std140`) into a single GPU buffer
+/// per std140 block rules, instead of uploading every uniform individually
+/// on each `DrawCall`. Bind it to a shader's named block through
+/// `ShaderParams`'s uniform block list.
+#[inline]
+pub fn create_uniform_buffer(
+    params: UniformBufferParams,
+    data: &[u8],
+) -> Result<UniformBufferHandle> {
+    ctx().create_uniform_buffer(params, data)
+}
+
+/// Replaces the contents of a uniform buffer object.
+#[inline]
+pub fn update_uniform_buffer(handle: UniformBufferHandle, data: &[u8]) -> Result<()> {
+    ctx().update_uniform_buffer(handle, data)
+}
+
+/// Delete the uniform buffer object.
+#[inline]
+pub fn delete_uniform_buffer(handle: UniformBufferHandle) {
+    ctx().delete_uniform_buffer(handle)
+}
+
+/// Wraps a GL texture id allocated outside this crate (a decoded video frame,
+/// a camera preview texture, a platform interop surface, ...) into a normal
+/// `TextureHandle`, so it can be sampled through the same
+/// `DrawCall::set_uniform_variable` path as any other texture.
+///
+/// No GPU object is created and no pixels are copied. `delete_texture` on the
+/// returned handle releases crayon's bookkeeping only; `raw_gl_id` is left
+/// alone for its original owner to free.
+#[inline]
+pub fn import_texture(raw_gl_id: u32, params: TextureParams) -> Result<TextureHandle> {
+    ctx().import_texture(raw_gl_id, params)
+}
+
 /// Create render texture object, which could be attached with a framebuffer.
 #[inline]
 pub fn create_render_texture(params: RenderTextureParams) -> Result<RenderTextureHandle> {
@@ -428,6 +513,88 @@ pub fn delete_render_texture(handle: RenderTextureHandle) {
     ctx().delete_render_texture(handle)
 }
 
+/// Selects one face of a `TextureDimension::Cube` texture, or one depth slice
+/// of a `TextureDimension::Texture3D` texture, for `update_texture_layer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureLayer {
+    CubeFace(CubeFace),
+    Slice(u32),
+}
+
+/// The six faces of a cube texture, in the same order OpenGL numbers
+/// `GL_TEXTURE_CUBE_MAP_POSITIVE_X` through `GL_TEXTURE_CUBE_MAP_NEGATIVE_Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+/// Update a contiguous subregion of one face of a cube texture, or one depth
+/// slice of a 3D texture. Use `update_texture` instead for a plain 2D texture.
+#[inline]
+pub fn update_texture_layer(
+    handle: TextureHandle,
+    layer: TextureLayer,
+    area: Aabb2<u32>,
+    data: &[u8],
+) -> Result<()> {
+    ctx().update_texture_layer(handle, layer, area, data)
+}
+
+/// Diagnostic information gathered during a single frame.
+#[derive(Debug, Clone, Default)]
+pub struct FrameInfo {
+    pub duration: ::std::time::Duration,
+    pub drawcall: usize,
+    pub triangles: usize,
+
+    /// GPU milliseconds spent per surface/pass name. Timer queries are
+    /// asynchronous, so these typically come from a frame or two ago, see
+    /// `gpu_timings`.
+    pub gpu_times: HashMap<String, f64>,
+}
+
+/// Returns the most recently available per-surface/pass GPU timings, in
+/// milliseconds, gathered via `glBeginQuery(GL_TIME_ELAPSED)`/`glEndQuery`
+/// pairs around each `Surface` (and optionally each named pass).
+///
+/// Timer queries are asynchronous, so a name's value here is typically the
+/// query result from a frame or two ago rather than the one currently being
+/// recorded, to avoid stalling the backend thread waiting on the GPU.
+///
+/// On GLES2 where `GL_EXT_disjoint_timer_query` is unavailable, a name maps
+/// to `None` instead of erroring.
+#[inline]
+pub fn gpu_timings() -> HashMap<String, Option<f64>> {
+    ctx().gpu_timings()
+}
+
+/// A handle to an in-flight `update_texture_async` upload, returned so the
+/// caller can learn when the backend is done reading `data` out of the PBO
+/// it was copied into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureUploadFence(pub(crate) u64);
+
+impl TextureUploadFence {
+    /// Returns `true` once the GPU has finished consuming the source data,
+    /// without blocking.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        ctx().is_upload_done(*self)
+    }
+
+    /// Blocks the calling thread until the GPU has finished consuming the
+    /// source data.
+    #[inline]
+    pub fn wait(&self) {
+        ctx().wait_upload(*self)
+    }
+}
+
 mod ins {
     use super::system::VideoSystem;
 